@@ -2,6 +2,9 @@ use cargo_snippet::snippet;
 
 use std::collections::HashMap;
 
+use crate::math::modulo::{inv_mod, mul_mod, pow_mod};
+use crate::math::prime::factorize;
+
 #[snippet(BabyStepGiantStep)]
 pub trait BSGSable {
     type T: std::fmt::Debug;
@@ -33,7 +36,8 @@ pub fn solve_bsgs<M: BSGSable>(a: M::T, b: M::T, mo: u64) -> Option<u64> {
     for j in 0..r {
         let x = &baby_step[j as usize];
         let k = M::unique_key_for(x);
-        baby_step_k2j.insert(k, j);
+        // 最小の解を返すため、同じ値なら小さい j を残す
+        baby_step_k2j.entry(k).or_insert(j);
     }
 
     // (a^-r)^i
@@ -63,6 +67,168 @@ pub fn solve_bsgs<M: BSGSable>(a: M::T, b: M::T, mo: u64) -> Option<u64> {
     return None;
 }
 
+#[snippet(name = "discrete_log", include = "BabyStepGiantStep, mod_u64")]
+fn gcd_u64(a: u64, b: u64) -> u64 {
+    if b == 0 {
+        a
+    } else {
+        gcd_u64(b, a % b)
+    }
+}
+
+#[snippet(name = "discrete_log", include = "BabyStepGiantStep, mod_u64")]
+struct ModMul;
+#[snippet(name = "discrete_log", include = "BabyStepGiantStep, mod_u64")]
+impl BSGSable for ModMul {
+    type T = u64;
+    type K = u64;
+    fn inv(x: &u64, mo: u64) -> u64 {
+        inv_mod(*x, mo)
+    }
+    fn unit() -> u64 {
+        1
+    }
+    fn multiply(x: &u64, y: &u64, mo: u64) -> u64 {
+        mul_mod(*x, *y, mo)
+    }
+    fn unique_key_for(x: &u64) -> u64 {
+        *x
+    }
+}
+
+/// a^x = b (mod m) となる最小の x >= 0
+/// a と m が互いに素でなくてもよい
+/// 計算量: O(root m)
+#[snippet(name = "discrete_log", include = "BabyStepGiantStep, mod_u64")]
+pub fn discrete_log(a: u64, b: u64, m: u64) -> Option<u64> {
+    if m == 1 {
+        return Some(0);
+    }
+    let (mut a, mut b, mut m) = (a % m, b % m, m);
+    // gcd(a, m) = g のとき a^x = b を (a/g) * a^(x-1) = b/g (mod m/g) に帰着する
+    let mut k = 0;
+    let mut add = 1 % m;
+    loop {
+        let g = gcd_u64(a, m);
+        if g == 1 {
+            break;
+        }
+        if b == add {
+            return Some(k);
+        }
+        if b % g != 0 {
+            return None;
+        }
+        b /= g;
+        m /= g;
+        k += 1;
+        add = mul_mod(add, a / g, m);
+        a %= m;
+    }
+    if m == 1 {
+        return Some(k);
+    }
+    let b = mul_mod(b, inv_mod(add, m), m);
+    solve_bsgs::<ModMul>(a, b, m).map(|x| x + k)
+}
+
+/// 素数 p の原始根のうち最小のもの
+/// 計算量: O(p^(1/4) + (p - 1 の素因数の個数) log p) 程度
+#[snippet(name = "primitive_root", include = "factorize, mod_u64")]
+pub fn primitive_root(p: u64) -> u64 {
+    if p == 2 {
+        return 1;
+    }
+    let fs = factorize(p - 1);
+    (2..)
+        .find(|&g| fs.iter().all(|&(q, _)| pow_mod(g, (p - 1) / q, p) != 1))
+        .unwrap()
+}
+
+/// x^q = a (mod p) の解を一つ求める (Adleman-Manders-Miller)
+/// q は p - 1 を割り切る素数、a は q 乗剰余
+#[snippet(name = "nth_root_mod", include = "discrete_log, factorize")]
+fn prime_root_mod(a: u64, q: u64, p: u64) -> u64 {
+    let mut s = 0;
+    let mut t = p - 1;
+    while t.is_multiple_of(q) {
+        t /= q;
+        s += 1;
+    }
+    // t * u + 1 ≡ 0 (mod q)
+    let u = (q - inv_mod(t % q, q)) % q;
+    let mut x = pow_mod(a, ((t as u128 * u as u128 + 1) / q as u128) as u64, p);
+    // x^q = a * err
+    let mut err = pow_mod(a, (t as u128 * u as u128 % (p - 1) as u128) as u64, p);
+    if err == 1 {
+        return x;
+    }
+    let z = (2..).find(|&z| pow_mod(z, (p - 1) / q, p) != 1).unwrap();
+    // c の位数は q^s, w の位数は q
+    let c = pow_mod(z, t, p);
+    let w = pow_mod(c, q.pow(s - 1), p);
+    let mut r = 1;
+    while r * r < q {
+        r += 1;
+    }
+    let mut baby = HashMap::new();
+    let mut cur = 1;
+    for i in 0..r {
+        baby.entry(cur).or_insert(i);
+        cur = mul_mod(cur, w, p);
+    }
+    let giant = inv_mod(cur, p);
+    while err != 1 {
+        // err の位数 q^j
+        let mut j = 0;
+        let mut e = err;
+        while e != 1 {
+            e = pow_mod(e, q, p);
+            j += 1;
+        }
+        // err^(q^(j-1)) = w^d
+        let mut target = pow_mod(err, q.pow(j - 1), p);
+        let mut d = 0;
+        for i in 0..r {
+            if let Some(&b) = baby.get(&target) {
+                d = i * r + b;
+                break;
+            }
+            target = mul_mod(target, giant, p);
+        }
+        // h = c^(-d q^(s-1-j)) として x <- x h, err <- err h^q
+        let h = pow_mod(inv_mod(c, p), d * q.pow(s - 1 - j), p);
+        x = mul_mod(x, h, p);
+        err = mul_mod(err, pow_mod(h, q, p), p);
+    }
+    x
+}
+
+/// x^k = a (mod p) となる x を一つ求める。p は素数
+/// 計算量: O(Σ e_i (log p + root q_i) log p) 程度 (gcd(k, p - 1) = Π q_i^e_i)
+#[snippet(name = "nth_root_mod", include = "discrete_log, factorize")]
+pub fn nth_root_mod(a: u64, k: u64, p: u64) -> Option<u64> {
+    let a = a % p;
+    if k == 0 {
+        return if a == 1 % p { Some(1 % p) } else { None };
+    }
+    if a == 0 || p == 2 {
+        return Some(a);
+    }
+    let g = gcd_u64(k, p - 1);
+    if pow_mod(a, (p - 1) / g, p) != 1 {
+        return None;
+    }
+    // x^k = a  <=>  x^g = a^((k/g)^-1 mod (p-1)/g) の解の一つ
+    let mut a = pow_mod(a, inv_mod((k / g) % ((p - 1) / g), (p - 1) / g), p);
+    for (q, e) in factorize(g) {
+        for _ in 0..e {
+            a = prime_root_mod(a, q, p);
+        }
+    }
+    Some(a)
+}
+
 #[test]
 fn test_bsgs() {
     struct M;
@@ -96,3 +262,56 @@ fn test_bsgs() {
         assert_eq!(ans, Some(x));
     }
 }
+
+#[test]
+fn test_discrete_log() {
+    for m in 1..60u64 {
+        for a in 0..m {
+            for b in 0..m {
+                let expected = (0..2 * m + 10).find(|&x| pow_mod(a, x, m) == b % m);
+                assert_eq!(discrete_log(a, b, m), expected, "{} {} {}", a, b, m);
+            }
+        }
+    }
+    assert_eq!(discrete_log(2, 854851041, 1_000_000_007), Some(11111111));
+    assert_eq!(discrete_log(6, 1, 1_000_000_007), Some(0));
+    assert_eq!(discrete_log(2, 0, 1 << 40), Some(40));
+    assert_eq!(discrete_log(4, 2, 1 << 40), None);
+}
+
+#[test]
+fn test_primitive_root() {
+    for p in (2..1000u64).filter(|&p| crate::math::prime::is_prime_u64(p)) {
+        let expected = (1..p)
+            .find(|&g| (1..p - 1).all(|e| pow_mod(g, e, p) != 1))
+            .unwrap();
+        assert_eq!(primitive_root(p), expected);
+    }
+    assert_eq!(primitive_root(998244353), 3);
+    assert_eq!(primitive_root(1_000_000_007), 5);
+}
+
+#[test]
+fn test_nth_root_mod() {
+    for p in (2..200u64).filter(|&p| crate::math::prime::is_prime_u64(p)) {
+        for k in 0..20 {
+            for a in 0..p {
+                let exists = (0..p).any(|x| pow_mod(x, k, p) == a);
+                match nth_root_mod(a, k, p) {
+                    Some(x) => assert_eq!(pow_mod(x, k, p), a, "{} {} {}", a, k, p),
+                    None => assert!(!exists, "{} {} {}", a, k, p),
+                }
+            }
+        }
+    }
+    let p = 998244353;
+    let mut rng = crate::xorshift::Xorshift::new();
+    for _ in 0..100 {
+        let x = rng.rand(p);
+        let k = rng.rand(1 << 30);
+        let a = pow_mod(x, k, p);
+        let y = nth_root_mod(a, k, p).unwrap();
+        assert_eq!(pow_mod(y, k, p), a);
+    }
+    assert_eq!(nth_root_mod(3, 1 << 23, p), None);
+}
@@ -9,6 +9,7 @@ pub mod graph;
 pub mod lexical_permutation;
 pub mod math;
 pub mod multi_set;
+pub mod polynomial;
 pub mod range_set;
 pub mod range_tree;
 pub mod sparse_table;
@@ -0,0 +1,534 @@
+use cargo_snippet::snippet;
+use std::cmp::Ordering;
+use std::ops::*;
+
+/// 多倍長符号付き整数
+/// 絶対値を 2^32 進で下位の桁から持つ。0 は mag が空で neg = false
+#[snippet("BigInt")]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct BigInt {
+  neg: bool,
+  mag: Vec<u32>,
+}
+
+#[snippet("BigInt")]
+fn trim(a: &mut Vec<u32>) {
+  while a.last() == Some(&0) {
+    a.pop();
+  }
+}
+
+#[snippet("BigInt")]
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+  a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+#[snippet("BigInt")]
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+  let (a, b) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+  let mut res = Vec::with_capacity(a.len() + 1);
+  let mut carry = 0u64;
+  for (i, &x) in a.iter().enumerate() {
+    let s = x as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+    res.push(s as u32);
+    carry = s >> 32;
+  }
+  if carry > 0 {
+    res.push(carry as u32);
+  }
+  res
+}
+
+/// a >= b
+#[snippet("BigInt")]
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+  let mut res = Vec::with_capacity(a.len());
+  let mut borrow = 0i64;
+  for (i, &x) in a.iter().enumerate() {
+    let mut s = x as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+    borrow = 0;
+    if s < 0 {
+      s += 1 << 32;
+      borrow = 1;
+    }
+    res.push(s as u32);
+  }
+  trim(&mut res);
+  res
+}
+
+#[snippet("BigInt")]
+fn mul_naive(a: &[u32], b: &[u32]) -> Vec<u32> {
+  if a.is_empty() || b.is_empty() {
+    return vec![];
+  }
+  let mut res = vec![0u32; a.len() + b.len()];
+  for (i, &x) in a.iter().enumerate() {
+    let mut carry = 0u64;
+    for (j, &y) in b.iter().enumerate() {
+      let t = x as u64 * y as u64 + res[i + j] as u64 + carry;
+      res[i + j] = t as u32;
+      carry = t >> 32;
+    }
+    res[i + b.len()] = carry as u32;
+  }
+  trim(&mut res);
+  res
+}
+
+/// res の shift 桁目から z を足し込む
+#[snippet("BigInt")]
+fn add_shifted(res: &mut [u32], z: &[u32], shift: usize) {
+  let mut carry = 0u64;
+  let mut i = 0;
+  while i < z.len() || carry > 0 {
+    let t = res[shift + i] as u64 + *z.get(i).unwrap_or(&0) as u64 + carry;
+    res[shift + i] = t as u32;
+    carry = t >> 32;
+    i += 1;
+  }
+}
+
+/// Karatsuba 法。O(n^1.58)
+/// 長さが 2 倍以上偏っていれば長い方を短い方の長さで区切って掛ける
+#[snippet("BigInt")]
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+  let (long, short) = if a.len() >= b.len() { (a, b) } else { (b, a) };
+  if short.len() <= 32 {
+    return mul_naive(a, b);
+  }
+  if short.len() * 2 < long.len() {
+    let mut res = vec![0u32; a.len() + b.len() + 1];
+    for (k, chunk) in long.chunks(short.len()).enumerate() {
+      let mut chunk = chunk.to_vec();
+      trim(&mut chunk);
+      add_shifted(&mut res, &mul_mag(&chunk, short), k * short.len());
+    }
+    trim(&mut res);
+    return res;
+  }
+  let m = long.len() / 2;
+  let split = |x: &[u32]| {
+    let (lo, hi) = x.split_at(m.min(x.len()));
+    let mut lo = lo.to_vec();
+    trim(&mut lo);
+    (lo, hi.to_vec())
+  };
+  let (a0, a1) = split(a);
+  let (b0, b1) = split(b);
+  let z0 = mul_mag(&a0, &b0);
+  let z2 = mul_mag(&a1, &b1);
+  let z1 = sub_mag(&sub_mag(&mul_mag(&add_mag(&a0, &a1), &add_mag(&b0, &b1)), &z0), &z2);
+  let mut res = vec![0u32; a.len() + b.len() + 1];
+  for (shift, z) in [(0, &z0), (m, &z1), (2 * m, &z2)] {
+    add_shifted(&mut res, z, shift);
+  }
+  trim(&mut res);
+  res
+}
+
+#[snippet("BigInt")]
+fn divmod_small(a: &[u32], d: u32) -> (Vec<u32>, u32) {
+  let mut q = vec![0u32; a.len()];
+  let mut r = 0u64;
+  for i in (0..a.len()).rev() {
+    let cur = (r << 32) | a[i] as u64;
+    q[i] = (cur / d as u64) as u32;
+    r = cur % d as u64;
+  }
+  trim(&mut q);
+  (q, r as u32)
+}
+
+/// Knuth の Algorithm D。b は空でない
+#[snippet("BigInt")]
+fn divmod_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+  if cmp_mag(a, b) == Ordering::Less {
+    return (vec![], a.to_vec());
+  }
+  if b.len() == 1 {
+    let (q, r) = divmod_small(a, b[0]);
+    let mut r = vec![r];
+    trim(&mut r);
+    return (q, r);
+  }
+  // b の最上位桁の最上位ビットが立つように正規化する
+  let s = b.last().unwrap().leading_zeros();
+  let shl = |x: &[u32], extra: usize| {
+    let mut res = vec![0u32; x.len() + extra];
+    for i in 0..x.len() {
+      let v = (x[i] as u64) << s;
+      res[i] |= v as u32;
+      if i + 1 < res.len() {
+        res[i + 1] |= (v >> 32) as u32;
+      }
+    }
+    res
+  };
+  let v = shl(b, 0);
+  let mut u = shl(a, 1);
+  let n = v.len();
+  let m = a.len() - n;
+  let base = 1u64 << 32;
+  let mut q = vec![0u32; m + 1];
+  for j in (0..=m).rev() {
+    let num = ((u[j + n] as u64) << 32) | u[j + n - 1] as u64;
+    let mut qhat = num / v[n - 1] as u64;
+    let mut rhat = num % v[n - 1] as u64;
+    while qhat >= base || qhat * v[n - 2] as u64 > ((rhat << 32) | u[j + n - 2] as u64) {
+      qhat -= 1;
+      rhat += v[n - 1] as u64;
+      if rhat >= base {
+        break;
+      }
+    }
+    // u[j..=j+n] -= qhat * v
+    let mut borrow = 0i64;
+    let mut carry = 0u64;
+    for i in 0..n {
+      let p = qhat * v[i] as u64 + carry;
+      carry = p >> 32;
+      let t = u[i + j] as i64 - borrow - (p & 0xffff_ffff) as i64;
+      u[i + j] = t as u32;
+      borrow = if t < 0 { 1 } else { 0 };
+    }
+    let t = u[j + n] as i64 - borrow - carry as i64;
+    u[j + n] = t as u32;
+    if t < 0 {
+      // 引きすぎたので一回足し戻す
+      qhat -= 1;
+      let mut carry = 0u64;
+      for i in 0..n {
+        let t = u[i + j] as u64 + v[i] as u64 + carry;
+        u[i + j] = t as u32;
+        carry = t >> 32;
+      }
+      u[j + n] = u[j + n].wrapping_add(carry as u32);
+    }
+    q[j] = qhat as u32;
+  }
+  trim(&mut q);
+  // 余りを元に戻す
+  let mut r = vec![0u32; n];
+  for i in 0..n {
+    let hi = if s > 0 && i + 1 < u.len() { (u[i + 1] as u64) << (32 - s) } else { 0 };
+    r[i] = ((u[i] >> s) as u64 | hi) as u32;
+  }
+  trim(&mut r);
+  (q, r)
+}
+
+#[snippet("BigInt")]
+impl BigInt {
+  fn from_parts(neg: bool, mag: Vec<u32>) -> Self {
+    let neg = neg && !mag.is_empty();
+    BigInt { neg, mag }
+  }
+  pub fn zero() -> Self {
+    Self::default()
+  }
+  pub fn is_zero(&self) -> bool {
+    self.mag.is_empty()
+  }
+  pub fn is_negative(&self) -> bool {
+    self.neg
+  }
+  pub fn abs(&self) -> Self {
+    Self::from_parts(false, self.mag.clone())
+  }
+  /// 0 方向に丸めた商と、被除数と同じ符号の余り (i64 の / と % と同じ)
+  pub fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+    assert!(!rhs.is_zero(), "division by zero");
+    let (q, r) = divmod_mag(&self.mag, &rhs.mag);
+    (Self::from_parts(self.neg != rhs.neg, q), Self::from_parts(self.neg, r))
+  }
+  /// 非負の最大公約数
+  pub fn gcd(&self, rhs: &Self) -> Self {
+    let (mut a, mut b) = (self.abs(), rhs.abs());
+    while !b.is_zero() {
+      let r = a.div_rem(&b).1;
+      a = b;
+      b = r;
+    }
+    a
+  }
+  pub fn pow(&self, e: u32) -> Self {
+    let mut ret = BigInt::from(1);
+    let mut base = self.clone();
+    let mut e = e;
+    while e > 0 {
+      if e & 1 == 1 {
+        ret = &ret * &base;
+      }
+      base = &base * &base;
+      e >>= 1;
+    }
+    ret
+  }
+  pub fn to_f64(&self) -> f64 {
+    let m = self.mag.iter().rev().fold(0f64, |acc, &x| acc * 4294967296.0 + x as f64);
+    if self.neg {
+      -m
+    } else {
+      m
+    }
+  }
+  /// i64 に収まるなら返す
+  pub fn to_i64(&self) -> Option<i64> {
+    if self.mag.len() > 2 {
+      return None;
+    }
+    let m = self.mag.iter().rev().fold(0u64, |acc, &x| (acc << 32) | x as u64);
+    if self.neg {
+      if m <= 1 << 63 {
+        Some((m as i64).wrapping_neg())
+      } else {
+        None
+      }
+    } else {
+      i64::try_from(m).ok()
+    }
+  }
+}
+
+#[snippet("BigInt")]
+impl From<i64> for BigInt {
+  fn from(v: i64) -> Self {
+    let m = v.unsigned_abs();
+    let mut mag = vec![m as u32, (m >> 32) as u32];
+    trim(&mut mag);
+    Self::from_parts(v < 0, mag)
+  }
+}
+
+#[snippet("BigInt")]
+impl Ord for BigInt {
+  fn cmp(&self, other: &Self) -> Ordering {
+    match (self.neg, other.neg) {
+      (false, true) => Ordering::Greater,
+      (true, false) => Ordering::Less,
+      (false, false) => cmp_mag(&self.mag, &other.mag),
+      (true, true) => cmp_mag(&other.mag, &self.mag),
+    }
+  }
+}
+#[snippet("BigInt")]
+impl PartialOrd for BigInt {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+#[snippet("BigInt")]
+impl<'a> Add<&'a BigInt> for &'a BigInt {
+  type Output = BigInt;
+  fn add(self, rhs: &BigInt) -> BigInt {
+    if self.neg == rhs.neg {
+      return BigInt::from_parts(self.neg, add_mag(&self.mag, &rhs.mag));
+    }
+    match cmp_mag(&self.mag, &rhs.mag) {
+      Ordering::Less => BigInt::from_parts(rhs.neg, sub_mag(&rhs.mag, &self.mag)),
+      _ => BigInt::from_parts(self.neg, sub_mag(&self.mag, &rhs.mag)),
+    }
+  }
+}
+#[snippet("BigInt")]
+impl<'a> Sub<&'a BigInt> for &'a BigInt {
+  type Output = BigInt;
+  fn sub(self, rhs: &BigInt) -> BigInt {
+    self + &-rhs
+  }
+}
+#[snippet("BigInt")]
+impl<'a> Mul<&'a BigInt> for &'a BigInt {
+  type Output = BigInt;
+  fn mul(self, rhs: &BigInt) -> BigInt {
+    BigInt::from_parts(self.neg != rhs.neg, mul_mag(&self.mag, &rhs.mag))
+  }
+}
+#[snippet("BigInt")]
+impl<'a> Div<&'a BigInt> for &'a BigInt {
+  type Output = BigInt;
+  fn div(self, rhs: &BigInt) -> BigInt {
+    self.div_rem(rhs).0
+  }
+}
+#[snippet("BigInt")]
+impl<'a> Rem<&'a BigInt> for &'a BigInt {
+  type Output = BigInt;
+  fn rem(self, rhs: &BigInt) -> BigInt {
+    self.div_rem(rhs).1
+  }
+}
+#[snippet("BigInt")]
+impl Neg for &BigInt {
+  type Output = BigInt;
+  fn neg(self) -> BigInt {
+    BigInt::from_parts(!self.neg, self.mag.clone())
+  }
+}
+#[snippet("BigInt")]
+impl Neg for BigInt {
+  type Output = BigInt;
+  fn neg(self) -> BigInt {
+    BigInt::from_parts(!self.neg, self.mag)
+  }
+}
+#[snippet("BigInt")]
+macro_rules! impl_bigint_owned_op {
+  ($tr:ident, $f:ident, $atr:ident, $af:ident) => {
+    impl $tr for BigInt {
+      type Output = BigInt;
+      fn $f(self, rhs: BigInt) -> BigInt {
+        (&self).$f(&rhs)
+      }
+    }
+    impl $atr for BigInt {
+      fn $af(&mut self, rhs: BigInt) {
+        *self = (&*self).$f(&rhs);
+      }
+    }
+  };
+}
+#[snippet("BigInt")]
+impl_bigint_owned_op!(Add, add, AddAssign, add_assign);
+#[snippet("BigInt")]
+impl_bigint_owned_op!(Sub, sub, SubAssign, sub_assign);
+#[snippet("BigInt")]
+impl_bigint_owned_op!(Mul, mul, MulAssign, mul_assign);
+#[snippet("BigInt")]
+impl_bigint_owned_op!(Div, div, DivAssign, div_assign);
+#[snippet("BigInt")]
+impl_bigint_owned_op!(Rem, rem, RemAssign, rem_assign);
+
+#[snippet("BigInt")]
+impl std::fmt::Display for BigInt {
+  fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+    // 10^9 進に変換する。O(n^2)
+    let mut chunks = vec![];
+    let mut mag = self.mag.clone();
+    while !mag.is_empty() {
+      let (q, r) = divmod_small(&mag, 1_000_000_000);
+      chunks.push(r);
+      mag = q;
+    }
+    let mut digits = chunks.pop().unwrap_or(0).to_string();
+    for c in chunks.iter().rev() {
+      digits += &format!("{:09}", c);
+    }
+    f.pad_integral(!self.neg, "", &digits)
+  }
+}
+
+#[snippet("BigInt")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBigIntError;
+
+#[snippet("BigInt")]
+impl std::str::FromStr for BigInt {
+  type Err = ParseBigIntError;
+  /// 10 進表記 (先頭に + か - があってもよい)。O(n^2)
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let (neg, digits) = match s.as_bytes().first() {
+      Some(b'-') => (true, &s[1..]),
+      Some(b'+') => (false, &s[1..]),
+      _ => (false, s),
+    };
+    if digits.is_empty() || !digits.bytes().all(|c| c.is_ascii_digit()) {
+      return Err(ParseBigIntError);
+    }
+    let mut mag: Vec<u32> = vec![];
+    let head = digits.len() % 9;
+    let chunks = std::iter::once(&digits[..head]).chain((head..digits.len()).step_by(9).map(|i| &digits[i..i + 9]));
+    for chunk in chunks.filter(|c| !c.is_empty()) {
+      // mag = mag * 10^len + chunk
+      let mut carry = chunk.parse::<u64>().unwrap();
+      let mul = 10u64.pow(chunk.len() as u32);
+      for x in mag.iter_mut() {
+        let t = *x as u64 * mul + carry;
+        *x = t as u32;
+        carry = t >> 32;
+      }
+      if carry > 0 {
+        mag.push(carry as u32);
+      }
+    }
+    trim(&mut mag);
+    Ok(BigInt::from_parts(neg, mag))
+  }
+}
+
+#[test]
+fn test_bigint_small() {
+  let vals = [0i64, 1, -1, 2, -3, 7, 1 << 31, -(1 << 32), (1 << 32) + 5, i32::MAX as i64 * 3, -987654321987, i64::MAX / 3, i64::MIN / 5];
+  for &a in &vals {
+    let ba = BigInt::from(a);
+    assert_eq!(ba.to_string(), a.to_string());
+    assert_eq!(a.to_string().parse::<BigInt>(), Ok(ba.clone()));
+    assert_eq!(ba.to_i64(), Some(a));
+    for &b in &vals {
+      let bb = BigInt::from(b);
+      assert_eq!(ba.cmp(&bb), a.cmp(&b));
+      let (x, y) = (a as i128, b as i128);
+      assert_eq!((&ba + &bb).to_string(), (x + y).to_string());
+      assert_eq!((&ba - &bb).to_string(), (x - y).to_string());
+      assert_eq!((&ba * &bb).to_string(), (x * y).to_string());
+      if b != 0 {
+        assert_eq!((&ba / &bb).to_string(), (x / y).to_string());
+        assert_eq!((&ba % &bb).to_string(), (x % y).to_string());
+      }
+    }
+  }
+  assert_eq!(BigInt::from(i64::MIN).to_i64(), Some(i64::MIN));
+  assert_eq!(BigInt::from(-(1 << 40) - 3).to_f64(), -1099511627779.0);
+  assert_eq!((BigInt::from(i64::MAX) + BigInt::from(1)).to_i64(), None);
+  assert_eq!(format!("{:>6}", BigInt::from(-42)), "   -42");
+  assert!("".parse::<BigInt>().is_err());
+  assert!("-".parse::<BigInt>().is_err());
+  assert!("12a".parse::<BigInt>().is_err());
+  assert_eq!("-000123".parse::<BigInt>(), Ok(BigInt::from(-123)));
+  assert_eq!("-0".parse::<BigInt>(), Ok(BigInt::zero()));
+}
+
+#[test]
+fn test_bigint_large() {
+  let ten50 = BigInt::from(10).pow(50);
+  assert_eq!(ten50.to_string(), format!("1{}", "0".repeat(50)));
+  assert_eq!((&ten50 * &ten50).to_string(), format!("1{}", "0".repeat(100)));
+  assert_eq!((&ten50 - &BigInt::from(1)).to_string(), "9".repeat(50));
+  let f30 = (1..=30).fold(BigInt::from(1), |acc, i| acc * BigInt::from(i));
+  assert_eq!(f30.to_string(), "265252859812191058636308480000000");
+  assert_eq!(f30.gcd(&BigInt::from(-1024)), BigInt::from(1024));
+
+  let mut rng = crate::xorshift::Xorshift::new();
+  let mut random = |len: usize| {
+    let mut mag = (0..len).map(|_| rng.rand(1 << 32) as u32).collect::<Vec<_>>();
+    trim(&mut mag);
+    BigInt::from_parts(rng.rand(2) == 1, mag)
+  };
+  for (la, lb) in [(1, 1), (3, 2), (40, 35), (100, 100), (200, 37), (150, 1), (300, 299), (1000, 40), (33, 700), (500, 240)] {
+    let a = random(la);
+    let b = random(lb);
+    assert_eq!(mul_mag(&a.mag, &b.mag), mul_naive(&a.mag, &b.mag));
+    if b.is_zero() {
+      continue;
+    }
+    let (q, r) = a.div_rem(&b);
+    assert_eq!(&(&q * &b) + &r, a);
+    assert!(cmp_mag(&r.mag, &b.mag) == Ordering::Less);
+    assert!(r.is_zero() || r.is_negative() == a.is_negative());
+    assert_eq!(a.to_string().parse::<BigInt>(), Ok(a.clone()));
+    let ab = &a * &b;
+    assert_eq!(&ab / &b, a);
+    assert!((&ab % &b).is_zero());
+    let g = a.gcd(&b);
+    assert!((&a % &g).is_zero() && (&b % &g).is_zero());
+    assert_eq!((&a / &g).gcd(&(&b / &g)), BigInt::from(1));
+  }
+  // Algorithm D の足し戻しが起きる例
+  let a = "170141183420855150474555134919112130560".parse::<BigInt>().unwrap();
+  let b = "39614081257132168796771975169".parse::<BigInt>().unwrap();
+  let (q, r) = a.div_rem(&b);
+  assert_eq!(q.to_string(), "4294967294");
+  assert_eq!(r.to_string(), "39614081257132168792477007874");
+}
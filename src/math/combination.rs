@@ -1,6 +1,9 @@
 use cargo_snippet::snippet;
 
-use super::modint::Mod;
+use super::crt::crt;
+use super::modint::{Mod, ModInt};
+use super::modulo::{inv_mod, pow_mod};
+use super::prime::factorize;
 
 const MOD: usize = 998244353;
 #[snippet("combination")]
@@ -23,20 +26,6 @@ fn combination(n: usize, r: usize) -> usize {
   }
   (res * pow(dev, MOD - 2)) % MOD
 }
-// let mut frac = vec![1; n + 1];
-// let mut invfrac = vec![1; n + 1];
-// for i in 0..n {
-//     frac[i + 1] = frac[i] * (i + 1);
-//     frac[i + 1] %= MOD;
-//     invfrac[i + 1] = invfrac[i] * pow(i + 1, MOD - 2);
-//     invfrac[i + 1] %= MOD;
-// }
-// let comb = |n: usize, r: usize| {
-//     if n < r {
-//         return 0;
-//     }
-//     ((frac[n] * invfrac[r]) % MOD) * invfrac[n - r] % MOD
-// };
 fn pow(x: usize, n: usize) -> usize {
   let mut ret = 1;
   let mut m = n;
@@ -63,3 +52,274 @@ fn binom() -> Vec<Vec<Mod>> {
   }
   binom
 }
+
+/// 階乗と階乗の逆元の表
+/// 足りなくなったら倍々に伸ばす
+#[snippet(name = "Binomial", include = "modint")]
+#[derive(Debug, Clone)]
+pub struct Binomial<T> {
+  fact: Vec<T>,
+  inv_fact: Vec<T>,
+}
+
+#[snippet(name = "Binomial", include = "modint")]
+impl<T: ModInt> Binomial<T> {
+  /// O(n)
+  pub fn new(n: usize) -> Self {
+    let mut b = Binomial {
+      fact: vec![T::from(1)],
+      inv_fact: vec![T::from(1)],
+    };
+    b.ensure(n);
+    b
+  }
+  /// n! まで計算済みにする
+  fn ensure(&mut self, n: usize) {
+    let len = self.fact.len();
+    if n < len {
+      return;
+    }
+    // 倍々で伸ばすときも法を超えない (M! = 0 となり逆元が取れない)
+    let new_len = (n + 1).max((len * 2).min(T::modulus() as usize));
+    for i in len..new_len {
+      let f = self.fact[i - 1] * T::from(i as i64);
+      self.fact.push(f);
+    }
+    self.inv_fact.resize(new_len, T::default());
+    self.inv_fact[new_len - 1] = self.fact[new_len - 1].inv();
+    for i in (len..new_len - 1).rev() {
+      self.inv_fact[i] = self.inv_fact[i + 1] * T::from(i as i64 + 1);
+    }
+  }
+  pub fn fact(&mut self, n: usize) -> T {
+    self.ensure(n);
+    self.fact[n]
+  }
+  pub fn inv_fact(&mut self, n: usize) -> T {
+    self.ensure(n);
+    self.inv_fact[n]
+  }
+  /// nCr
+  pub fn comb(&mut self, n: usize, r: usize) -> T {
+    if n < r {
+      return T::default();
+    }
+    self.ensure(n);
+    self.fact[n] * self.inv_fact[r] * self.inv_fact[n - r]
+  }
+  /// nPr
+  pub fn perm(&mut self, n: usize, r: usize) -> T {
+    if n < r {
+      return T::default();
+    }
+    self.ensure(n);
+    self.fact[n] * self.inv_fact[n - r]
+  }
+  /// nHr: n 種類から重複を許して r 個選ぶ
+  pub fn homo(&mut self, n: usize, r: usize) -> T {
+    if n == 0 {
+      return if r == 0 { T::from(1) } else { T::default() };
+    }
+    self.comb(n + r - 1, r)
+  }
+  /// n 番目のカタラン数
+  pub fn catalan(&mut self, n: usize) -> T {
+    self.comb(2 * n, n) - self.comb(2 * n, n + 1)
+  }
+  /// (k_1 + ... + k_m)! / (k_1! ... k_m!)
+  pub fn multinomial(&mut self, ks: &[usize]) -> T {
+    let n = ks.iter().sum::<usize>();
+    self.ensure(n);
+    ks.iter().fold(self.fact[n], |acc, &k| acc * self.inv_fact[k])
+  }
+}
+
+/// 素数 p を法とする二項係数 (Lucas の定理)
+/// 前計算 O(p), クエリ O(log_p n)
+#[snippet(name = "binom_mod", include = "factorize, crt, mod_u64")]
+pub struct Lucas {
+  p: u64,
+  fact: Vec<u64>,
+  inv_fact: Vec<u64>,
+}
+
+#[snippet(name = "binom_mod", include = "factorize, crt, mod_u64")]
+impl Lucas {
+  pub fn new(p: u64) -> Self {
+    let n = p as usize;
+    let mut fact = vec![1 % p; n];
+    for i in 1..n {
+      fact[i] = fact[i - 1] * i as u64 % p;
+    }
+    let mut inv_fact = vec![1 % p; n];
+    inv_fact[n - 1] = pow_mod(fact[n - 1], p - 2, p);
+    for i in (1..n).rev() {
+      inv_fact[i - 1] = inv_fact[i] * i as u64 % p;
+    }
+    Lucas { p, fact, inv_fact }
+  }
+  pub fn comb(&self, n: u64, r: u64) -> u64 {
+    let p = self.p;
+    let (mut n, mut r) = (n, r);
+    let mut ret = 1 % p;
+    while r > 0 {
+      let (ni, ri) = ((n % p) as usize, (r % p) as usize);
+      if ni < ri {
+        return 0;
+      }
+      ret = ret * self.fact[ni] % p * self.inv_fact[ri] % p * self.inv_fact[ni - ri] % p;
+      n /= p;
+      r /= p;
+    }
+    ret
+  }
+}
+
+/// 素数冪 p^q を法とする二項係数
+/// n! = p^e * (p と互いに素な部分) と分けて計算する
+/// 前計算 O(p^q), クエリ O(log n)
+#[snippet(name = "binom_mod", include = "factorize, crt, mod_u64")]
+pub struct BinomPrimePower {
+  p: u64,
+  q: u32,
+  pq: u64,
+  /// fact[x] = (1..=x のうち p と互いに素なものの積) mod p^q
+  fact: Vec<u64>,
+}
+
+#[snippet(name = "binom_mod", include = "factorize, crt, mod_u64")]
+impl BinomPrimePower {
+  pub fn new(p: u64, q: u32) -> Self {
+    let pq = p.pow(q);
+    let mut fact = vec![1 % pq; pq as usize + 1];
+    for i in 1..=pq as usize {
+      fact[i] = if (i as u64).is_multiple_of(p) { fact[i - 1] } else { fact[i - 1] * i as u64 % pq };
+    }
+    BinomPrimePower { p, q, pq, fact }
+  }
+  /// (n! の p と互いに素な部分 mod p^q, n! の p の指数)
+  fn fact_p_free(&self, n: u64) -> (u64, u64) {
+    let mut ret = 1 % self.pq;
+    let mut e = 0;
+    let mut n = n;
+    while n > 0 {
+      ret = ret * pow_mod(self.fact[self.pq as usize], n / self.pq, self.pq) % self.pq;
+      ret = ret * self.fact[(n % self.pq) as usize] % self.pq;
+      n /= self.p;
+      e += n;
+    }
+    (ret, e)
+  }
+  pub fn comb(&self, n: u64, r: u64) -> u64 {
+    if n < r {
+      return 0;
+    }
+    let (a, ea) = self.fact_p_free(n);
+    let (b, eb) = self.fact_p_free(r);
+    let (c, ec) = self.fact_p_free(n - r);
+    let e = ea - eb - ec;
+    if e >= self.q as u64 {
+      return 0;
+    }
+    let pq = self.pq;
+    a * inv_mod(b * c % pq, pq) % pq * self.p.pow(e as u32) % pq
+  }
+}
+
+/// nCr mod p (p は素数)。Lucas の定理
+#[snippet(name = "binom_mod", include = "factorize, crt, mod_u64")]
+pub fn binom_mod_prime(n: u64, r: u64, p: u64) -> u64 {
+  Lucas::new(p).comb(n, r)
+}
+
+/// nCr mod m (m は任意)
+/// m を素数冪に分解して各々で計算し CRT で復元する。前計算 O(m)
+#[snippet(name = "binom_mod", include = "factorize, crt, mod_u64")]
+pub fn binom_mod(n: u64, r: u64, m: u64) -> u64 {
+  let rm = factorize(m)
+    .into_iter()
+    .map(|(p, q)| {
+      let b = BinomPrimePower::new(p, q);
+      (b.comb(n, r) as i64, b.pq as i64)
+    })
+    .collect::<Vec<_>>();
+  crt(&rm).unwrap().0 as u64
+}
+
+
+
+#[test]
+fn test_binomial() {
+  use super::modint::{ModInt1000000007, StaticModInt};
+  let mut b = Binomial::<Mod>::new(0);
+  let mut pascal = vec![vec![Mod::new(0); 200]; 200];
+  for n in 0..200 {
+    pascal[n][0] = Mod::new(1);
+    for r in 1..=n {
+      pascal[n][r] = pascal[n - 1][r - 1] + pascal[n - 1][r];
+    }
+  }
+  for n in 0..200 {
+    for r in 0..200 {
+      assert_eq!(b.comb(n, r), pascal[n][r]);
+      assert_eq!(b.perm(n, r), if n < r { Mod::new(0) } else { pascal[n][r] * b.fact(r) });
+      if n >= 1 && n + r <= 200 {
+        assert_eq!(b.homo(n, r), pascal[n + r - 1][r]);
+      }
+    }
+    assert_eq!(b.fact(n) * b.inv_fact(n), Mod::new(1));
+  }
+  assert_eq!(b.homo(0, 0), Mod::new(1));
+  assert_eq!(b.homo(0, 3), Mod::new(0));
+  let catalan = [1, 1, 2, 5, 14, 42, 132, 429, 1430, 4862];
+  for (n, &c) in catalan.iter().enumerate() {
+    assert_eq!(b.catalan(n), Mod::new(c));
+  }
+  assert_eq!(b.multinomial(&[2, 1, 1]), Mod::new(12));
+  assert_eq!(b.multinomial(&[]), Mod::new(1));
+
+  let mut b = Binomial::<ModInt1000000007>::new(10);
+  assert_eq!(b.comb(100000, 50000).val(), 149033233);
+  // 小さい素数の法で伸ばしても法を超えない
+  let mut b = Binomial::<StaticModInt<10007>>::new(6000);
+  assert_eq!(b.comb(7000, 3).val(), 7467);
+  assert_eq!(b.comb(10006, 5000), Binomial::<StaticModInt<10007>>::new(10006).comb(10006, 5000));
+  assert_eq!(combination(100000, 50000), Binomial::<Mod>::new(0).comb(100000, 50000).val() as usize);
+}
+
+#[test]
+fn test_binom_mod() {
+  // C(n, r) <= C(60, 30) < 2^60
+  let mut pascal = vec![vec![0u64; 61]; 61];
+  for n in 0..=60 {
+    pascal[n][0] = 1;
+    for r in 1..=n {
+      pascal[n][r] = pascal[n - 1][r - 1] + pascal[n - 1][r];
+    }
+  }
+  for m in 1..=64 {
+    for n in 0..=60u64 {
+      for r in 0..=61u64 {
+        let expected = if r > n { 0 } else { pascal[n as usize][r as usize] % m };
+        assert_eq!(binom_mod(n, r, m), expected, "{} {} {}", n, r, m);
+      }
+    }
+  }
+  for p in [2, 3, 5, 7, 11, 13, 59] {
+    let lucas = Lucas::new(p);
+    for n in 0..=60u64 {
+      for r in 0..=n {
+        assert_eq!(lucas.comb(n, r), pascal[n as usize][r as usize] % p);
+      }
+    }
+  }
+  assert_eq!(binom_mod_prime(1 << 40, 1 << 20, 2), 0);
+  assert_eq!(binom_mod_prime((1 << 40) - 1, 1 << 20, 2), 1);
+  assert_eq!(binom_mod_prime(1 << 40, 3, 2), 0);
+  let n = 1_000_000_000_000_000_000;
+  let r = 123_456_789_012_345;
+  assert_eq!(binom_mod(n, r, 10007), binom_mod_prime(n, r, 10007));
+  assert_eq!(binom_mod(100000, 50000, 1_000_000), 416640);
+  assert_eq!(binom_mod(1000000, 123456, 1_000_000), 250000);
+  assert_eq!(binom_mod(1000000, 777, (1 << 20) * 243), 246730752);
+}
@@ -1,3 +1,5 @@
+use cargo_snippet::snippet;
+
 pub fn modinv(a: usize, modulo: usize) -> usize {
   let mut a = a as i64;
   let modulo = modulo as i64;
@@ -30,9 +32,75 @@ fn gauss_crt(rm: &Vec<(usize, usize)>) -> (usize, usize) {
   (retv % mm, mm)
 }
 
+/// ax + by = gcd(a, b) となる (gcd(a, b), x, y)
+#[snippet("crt")]
+fn extgcd(a: i128, b: i128) -> (i128, i128, i128) {
+  if b == 0 {
+    (a, 1, 0)
+  } else {
+    let (g, x, y) = extgcd(b, a % b);
+    (g, y, x - (a / b) * y)
+  }
+}
+
+/// x = r_i (mod m_i) を全て満たす x を (x mod lcm, lcm) で返す。矛盾すれば None
+/// 法は互いに素でなくてよい。lcm は i64 に収まること (収まらなければ panic)
+/// 空なら (0, 1)
+#[snippet("crt")]
+pub fn crt(rm: &[(i64, i64)]) -> Option<(i64, i64)> {
+  let (mut r0, mut m0) = (0i128, 1i128);
+  for &(r, m) in rm {
+    assert!(m >= 1);
+    let (mut r1, mut m1) = ((r as i128).rem_euclid(m as i128), m as i128);
+    if m0 < m1 {
+      std::mem::swap(&mut r0, &mut r1);
+      std::mem::swap(&mut m0, &mut m1);
+    }
+    if m0 % m1 == 0 {
+      if r0 % m1 != r1 {
+        return None;
+      }
+      continue;
+    }
+    // r0 + m0 t = r1 (mod m1)
+    let (g, p, _) = extgcd(m0, m1);
+    if (r1 - r0) % g != 0 {
+      return None;
+    }
+    let u1 = m1 / g;
+    let t = ((r1 - r0) / g % u1 * p).rem_euclid(u1);
+    r0 += t * m0;
+    m0 *= u1;
+    assert!(m0 <= i64::MAX as i128, "lcm of moduli exceeds i64");
+    r0 = r0.rem_euclid(m0);
+  }
+  Some((r0 as i64, m0 as i64))
+}
+
+/// 法が互いに素なとき x = r_i (mod m_i) を満たす最小の x >= 0 を modulo で割った余り
+/// 多倍長にせずに x mod modulo を求める。O(n^2)
+#[snippet("crt")]
+pub fn garner(rm: &[(i64, i64)], modulo: i64) -> i64 {
+  let n = rm.len();
+  // coef[i] = m_0 ... m_{i-1} mod m_i, constants[i] = x_0 + ... (mod m_i)
+  let mut coef = vec![1i128; n + 1];
+  let mut constants = vec![0i128; n + 1];
+  let moduli = rm.iter().map(|&(_, m)| m as i128).chain([modulo as i128]).collect::<Vec<_>>();
+  for (i, &(r, m)) in rm.iter().enumerate() {
+    let m = m as i128;
+    let (_, inv, _) = extgcd(coef[i], m);
+    let t = ((r as i128 - constants[i]) * inv).rem_euclid(m);
+    for j in i + 1..=n {
+      constants[j] = (constants[j] + coef[j] * t) % moduli[j];
+      coef[j] = coef[j] * m % moduli[j];
+    }
+  }
+  constants[n] as i64
+}
+
 #[cfg(test)]
 mod test {
-  use crate::math::crt::gauss_crt;
+  use crate::math::crt::{crt as general_crt, garner, gauss_crt};
   #[test]
   fn crt() {
     let rm = vec![(2, 3), (3, 5), (3, 7)];
@@ -49,4 +117,51 @@ mod test {
     assert_eq!(106 % 7, 1);
     assert_eq!(106 % 16, 10);
   }
+
+  #[test]
+  fn crt_general() {
+    assert_eq!(general_crt(&[]), Some((0, 1)));
+    assert_eq!(general_crt(&[(2, 3), (3, 5), (3, 7)]), Some((38, 105)));
+    assert_eq!(general_crt(&[(-1, 4), (5, 6)]), Some((11, 12)));
+    assert_eq!(general_crt(&[(1, 4), (2, 6)]), None);
+    for m1 in 1..20i64 {
+      for m2 in 1..20i64 {
+        for r1 in 0..m1 {
+          for r2 in 0..m2 {
+            let l = m1 * m2 / gcd(m1, m2);
+            let expected = (0..l).find(|x| x % m1 == r1 && x % m2 == r2).map(|x| (x, l));
+            assert_eq!(general_crt(&[(r1, m1), (r2, m2)]), expected);
+          }
+        }
+      }
+    }
+    let (m1, m2) = (999_999_937i64, 1_000_000_007);
+    let x = 123_456_789_012_345_678i64 % (m1 * m2);
+    assert_eq!(general_crt(&[(x % m1, m1), (x % m2, m2)]), Some((x, m1 * m2)));
+    fn gcd(a: i64, b: i64) -> i64 {
+      if b == 0 {
+        a
+      } else {
+        gcd(b, a % b)
+      }
+    }
+  }
+
+  #[test]
+  #[should_panic(expected = "lcm of moduli exceeds i64")]
+  fn crt_lcm_overflow() {
+    general_crt(&[(1, 4_000_000_000), (2, 3_000_000_001)]);
+  }
+
+  #[test]
+  fn garner_test() {
+    let ms = [167772161i64, 469762049, 1224736769];
+    let x: i128 = 12_345_678_901_234_567_890_123;
+    let rm = ms.iter().map(|&m| ((x % m as i128) as i64, m)).collect::<Vec<_>>();
+    for modulo in [998244353i64, 1_000_000_007, 2, 1] {
+      assert_eq!(garner(&rm, modulo), (x % modulo as i128) as i64);
+    }
+    assert_eq!(garner(&[(2, 3), (3, 5), (3, 7)], 1000), 38);
+    assert_eq!(garner(&[(2, 3), (3, 5), (3, 7)], 10), 8);
+  }
 }
@@ -0,0 +1,247 @@
+use super::modint::ModInt;
+use cargo_snippet::snippet;
+
+/// n(n-1)/2 mod 2^64
+/// 積が溢れる前に偶数の方を 2 で割っておく
+#[snippet("floor_sum")]
+fn triangular(n: u64) -> u64 {
+  if n & 1 == 0 {
+    (n / 2).wrapping_mul(n.wrapping_sub(1))
+  } else {
+    n.wrapping_mul((n - 1) / 2)
+  }
+}
+
+/// ACL の floor_sum_unsigned
+/// 答えは mod 2^64 で返す
+#[snippet("floor_sum")]
+fn floor_sum_unsigned(mut n: u64, mut m: u64, mut a: u64, mut b: u64) -> u64 {
+  let mut ans = 0u64;
+  loop {
+    if a >= m {
+      ans = ans.wrapping_add(triangular(n).wrapping_mul(a / m));
+      a %= m;
+    }
+    if b >= m {
+      ans = ans.wrapping_add(n.wrapping_mul(b / m));
+      b %= m;
+    }
+    let y_max = a as u128 * n as u128 + b as u128;
+    if y_max < m as u128 {
+      break;
+    }
+    n = (y_max / m as u128) as u64;
+    b = (y_max % m as u128) as u64;
+    std::mem::swap(&mut m, &mut a);
+  }
+  ans
+}
+
+/// Σ_{i=0}^{n-1} floor((a * i + b) / m)
+/// a, b は負でもよい。答えが i64 に収まらない場合は mod 2^64 での値になる
+/// O(log m)
+#[snippet("floor_sum")]
+pub fn floor_sum(n: i64, m: i64, a: i64, b: i64) -> i64 {
+  assert!(0 <= n && 1 <= m);
+  let mut ans = 0i64;
+  let (mut a, mut b) = (a, b);
+  if a < 0 || a >= m {
+    let q = a.div_euclid(m);
+    ans = ans.wrapping_add(triangular(n as u64).wrapping_mul(q as u64) as i64);
+    a -= q * m;
+  }
+  if b < 0 || b >= m {
+    let q = b.div_euclid(m);
+    ans = ans.wrapping_add(n.wrapping_mul(q));
+    b -= q * m;
+  }
+  ans.wrapping_add(floor_sum_unsigned(n as u64, m as u64, a as u64, b as u64) as i64)
+}
+
+/// 万能ユークリッドで使うモノイド
+#[snippet("universal_euclid")]
+pub trait Monoid: Copy {
+  fn identity() -> Self;
+  fn op(l: Self, r: Self) -> Self;
+}
+
+#[snippet("universal_euclid")]
+fn monoid_pow<T: Monoid>(mut a: T, mut e: u128) -> T {
+  let mut ret = T::identity();
+  while e > 0 {
+    if e & 1 == 1 {
+      ret = T::op(ret, a);
+    }
+    a = T::op(a, a);
+    e >>= 1;
+  }
+  ret
+}
+
+/// x = 1..=l について U^(y(x) - y(x-1)) R を並べた積 (y(x) = floor((p * x + r) / q), 0 <= r < q)
+#[snippet("universal_euclid")]
+fn euclid_rec<T: Monoid>(p: u128, q: u128, r: u128, l: u128, u: T, rr: T) -> T {
+  if l == 0 {
+    return T::identity();
+  }
+  if p >= q {
+    return euclid_rec(p % q, q, r, l, u, T::op(monoid_pow(u, p / q), rr));
+  }
+  let m = (p * l + r) / q;
+  if m == 0 {
+    return monoid_pow(rr, l);
+  }
+  let cnt = l - (q * m - r - 1) / p;
+  let mid = euclid_rec(q, p, (q - r - 1) % p, m - 1, rr, u);
+  let ret = T::op(monoid_pow(rr, (q - r - 1) / p), u);
+  T::op(T::op(ret, mid), monoid_pow(rr, cnt))
+}
+
+/// 万能ユークリッド
+/// f(i) = floor((a * i + b) / m) として、i = 0..n について U^(f(i) - f(i-1)) R を並べた積を返す (f(-1) = 0)
+/// i 番目の R の直前までに U はちょうど f(i) 個、R は i 個並んでいる
+/// O(log(max(a, m)) * (モノイド演算の log))
+#[snippet("universal_euclid")]
+pub fn universal_euclid<T: Monoid>(n: u64, m: u64, a: u64, b: u64, u: T, r: T) -> T {
+  assert!(m >= 1);
+  if n == 0 {
+    return T::identity();
+  }
+  let head = T::op(monoid_pow(u, (b / m) as u128), r);
+  let tail = euclid_rec(a as u128, m as u128, (b % m) as u128, (n - 1) as u128, u, r);
+  T::op(head, tail)
+}
+
+/// floor_sums 用のモノイド
+/// x: R の個数, y: U の個数, 各 R の時点での (x, y) について Σx, Σy, Σxy, Σy^2
+#[snippet(name = "floor_sums", include = "universal_euclid, modint")]
+#[derive(Debug, Clone, Copy)]
+struct FloorSumNode<T> {
+  x: T,
+  y: T,
+  sum_x: T,
+  sum_y: T,
+  sum_xy: T,
+  sum_y2: T,
+}
+
+#[snippet(name = "floor_sums", include = "universal_euclid, modint")]
+impl<T: ModInt> Monoid for FloorSumNode<T> {
+  fn identity() -> Self {
+    let z = T::default();
+    FloorSumNode {
+      x: z,
+      y: z,
+      sum_x: z,
+      sum_y: z,
+      sum_xy: z,
+      sum_y2: z,
+    }
+  }
+  fn op(l: Self, r: Self) -> Self {
+    FloorSumNode {
+      x: l.x + r.x,
+      y: l.y + r.y,
+      sum_x: l.sum_x + r.sum_x + l.x * r.x,
+      sum_y: l.sum_y + r.sum_y + l.y * r.x,
+      sum_xy: l.sum_xy + r.sum_xy + l.x * r.sum_y + l.y * r.sum_x + l.x * l.y * r.x,
+      sum_y2: l.sum_y2 + r.sum_y2 + T::from(2) * l.y * r.sum_y + l.y * l.y * r.x,
+    }
+  }
+}
+
+/// f(i) = floor((a * i + b) / m) として (Σf(i), Σi*f(i), Σf(i)^2) (i = 0..n)
+/// a, b >= 0
+#[snippet(name = "floor_sums", include = "universal_euclid, modint")]
+pub fn floor_sums<T: ModInt>(n: u64, m: u64, a: u64, b: u64) -> (T, T, T) {
+  let mut u = FloorSumNode::identity();
+  u.y = T::from(1);
+  let mut r = FloorSumNode::identity();
+  r.x = T::from(1);
+  let ret = universal_euclid(n, m, a, b, u, r);
+  (ret.sum_y, ret.sum_xy, ret.sum_y2)
+}
+
+/// min_{0 <= i < n} (a * i + b) mod m
+/// (a * i + b) mod m >= t となる i の個数を floor_sum で数えて二分探索する
+/// O(log^2 m)
+#[snippet(name = "min_of_mod_of_linear", include = "floor_sum")]
+pub fn min_of_mod_of_linear(n: i64, m: i64, a: i64, b: i64) -> i64 {
+  assert!(1 <= n && 1 <= m);
+  let (a, b) = (a.rem_euclid(m), b.rem_euclid(m));
+  let base = floor_sum(n, m, a, b);
+  // floor((v - t) / m) + 1 - floor(v / m) は v mod m >= t のとき 1
+  // b + m - t は m > i64::MAX / 2 で溢れるので b - t (>= -m) を渡す
+  let count_ge = |t: i64| floor_sum(n, m, a, b - t).wrapping_add(n).wrapping_sub(base);
+  let (mut ok, mut ng) = (0, m);
+  while ng - ok > 1 {
+    let mid = ok + (ng - ok) / 2;
+    if count_ge(mid) == n {
+      ok = mid;
+    } else {
+      ng = mid;
+    }
+  }
+  ok
+}
+
+#[test]
+fn test_floor_sum() {
+  let mut rng = crate::xorshift::Xorshift::new();
+  for _ in 0..2000 {
+    let n = rng.rand(30) as i64;
+    let m = rng.rand(20) as i64 + 1;
+    let a = rng.rand(100) as i64 - 50;
+    let b = rng.rand(100) as i64 - 50;
+    let naive: i64 = (0..n).map(|i| (a * i + b).div_euclid(m)).sum();
+    assert_eq!(floor_sum(n, m, a, b), naive);
+    if n > 0 {
+      let naive = (0..n).map(|i| (a * i + b).rem_euclid(m)).min().unwrap();
+      assert_eq!(min_of_mod_of_linear(n, m, a, b), naive);
+    }
+  }
+  assert_eq!(floor_sum(1_000_000_000, 1_000_000_000, 999_999_999, 999_999_999), 499999999500000000);
+  // n >= 2^32 で n(n-1) が溢れる場合も mod 2^64 で正しい
+  for n in [1i64 << 32, (1 << 32) + 1, 1 << 33, (1 << 40) + 7, i64::MAX] {
+    let tri = n as i128 * (n as i128 - 1) / 2;
+    for (a, b) in [(1, 0), (-5, 3), (7, -11)] {
+      let expected = (a as i128).wrapping_mul(tri).wrapping_add(b as i128 * n as i128) as i64;
+      assert_eq!(floor_sum(n, 1, a, b), expected);
+    }
+    // floor(i / 2) の和は内部の再帰で n / 2 に対する三角数になる
+    let h = n as i128 / 2;
+    let expected = if n % 2 == 0 { h * (h - 1) } else { h * h } as i64;
+    assert_eq!(floor_sum(n, 2, 1, 0), expected);
+  }
+  assert_eq!(floor_sum(1 << 33, 1, 1, 0), -(1i64 << 32));
+  assert_eq!(min_of_mod_of_linear(1_000_000_000, 1_000_000_007, 123_456_789, 5), 0);
+  // m > i64::MAX / 2
+  assert_eq!(min_of_mod_of_linear(3, 6_000_000_000_000_000_000, 1, 5_999_999_999_999_999_999), 0);
+  assert_eq!(min_of_mod_of_linear(3, i64::MAX, i64::MAX - 1, 5), 3);
+  for _ in 0..20 {
+    let m = rng.rand(1 << 40) as i64 + 1;
+    let a = rng.rand(1 << 40) as i64;
+    let b = rng.rand(1 << 40) as i64;
+    let naive = (0..3000).map(|i| (a * i + b).rem_euclid(m)).min().unwrap();
+    assert_eq!(min_of_mod_of_linear(3000, m, a, b), naive);
+  }
+}
+
+#[test]
+fn test_floor_sums() {
+  use super::modint::Mod;
+  let mut rng = crate::xorshift::Xorshift::new();
+  for _ in 0..2000 {
+    let n = rng.rand(30);
+    let m = rng.rand(20) + 1;
+    let a = rng.rand(50);
+    let b = rng.rand(50);
+    let f = |i: u64| ((a * i + b) / m) as i64;
+    let s0: i64 = (0..n).map(f).sum();
+    let s1: i64 = (0..n).map(|i| i as i64 * f(i)).sum();
+    let s2: i64 = (0..n).map(|i| f(i) * f(i)).sum();
+    assert_eq!(floor_sums::<Mod>(n, m, a, b), (Mod::new(s0), Mod::new(s1), Mod::new(s2)));
+  }
+  let (s0, _, _) = floor_sums::<Mod>(1_000_000_000, 1_000_000_000, 999_999_999, 999_999_999);
+  assert_eq!(s0, Mod::new(499999999500000000));
+}
@@ -1,54 +1,243 @@
-fn matrix_product(a: &Vec<Vec<usize>>, b: &Vec<Vec<usize>>) -> Vec<Vec<usize>> {
-  let mut ret = vec![vec![0; a[0].len()]; a.len()];
-  for i in 0..a.len() {
-    for j in 0..a[0].len() {
-      for k in 0..b[0].len() {
-        ret[i][k] += a[i][j] * b[j][k];
-      }
-    }
+use super::modint::ModInt;
+use std::ops::{Index, IndexMut, Mul};
+
+/// 行列の成分に使う半環
+/// add は可換モノイド (単位元 zero)、mul はモノイド (単位元 one) で、zero は mul の零元
+pub trait Semiring: Copy {
+  fn zero() -> Self;
+  fn one() -> Self;
+  fn add(l: Self, r: Self) -> Self;
+  fn mul(l: Self, r: Self) -> Self;
+}
+
+impl<T: ModInt> Semiring for T {
+  fn zero() -> Self {
+    T::default()
+  }
+  fn one() -> Self {
+    T::from(1)
+  }
+  fn add(l: Self, r: Self) -> Self {
+    l + r
+  }
+  fn mul(l: Self, r: Self) -> Self {
+    l * r
+  }
+}
+
+impl Semiring for f64 {
+  fn zero() -> Self {
+    0.0
+  }
+  fn one() -> Self {
+    1.0
+  }
+  fn add(l: Self, r: Self) -> Self {
+    l + r
+  }
+  fn mul(l: Self, r: Self) -> Self {
+    l * r
+  }
+}
+
+/// (or, and) 半環。到達可能性など
+impl Semiring for bool {
+  fn zero() -> Self {
+    false
+  }
+  fn one() -> Self {
+    true
+  }
+  fn add(l: Self, r: Self) -> Self {
+    l | r
+  }
+  fn mul(l: Self, r: Self) -> Self {
+    l & r
   }
-  ret
 }
-fn vector_product(a: &Vec<Vec<i64>>, b: &Vec<i64>) -> Vec<i64> {
-  let mut ret = vec![0; a.len()];
-  for i in 0..a.len() {
-    for j in 0..b.len() {
-      ret[i] += a[i][j] * b[j];
+
+/// (min, +) 半環。i64::MAX を到達不能 (INF) として扱う
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MinPlus(pub i64);
+
+impl MinPlus {
+  pub const INF: MinPlus = MinPlus(i64::MAX);
+}
+
+impl Semiring for MinPlus {
+  fn zero() -> Self {
+    Self::INF
+  }
+  fn one() -> Self {
+    MinPlus(0)
+  }
+  fn add(l: Self, r: Self) -> Self {
+    l.min(r)
+  }
+  fn mul(l: Self, r: Self) -> Self {
+    if l == Self::INF || r == Self::INF {
+      Self::INF
+    } else {
+      MinPlus(l.0 + r.0)
     }
   }
-  ret
 }
-fn matrix_pow(x: &Vec<Vec<usize>>, k: usize) -> Vec<Vec<usize>> {
-  let mut ret = vec![vec![0; x.len()]; x.len()];
-  for i in 0..x.len() {
-    ret[i][i] = 1;
+
+/// (max, +) 半環。i64::MIN を到達不能 (-INF) として扱う
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct MaxPlus(pub i64);
+
+impl MaxPlus {
+  pub const NEG_INF: MaxPlus = MaxPlus(i64::MIN);
+}
+
+impl Semiring for MaxPlus {
+  fn zero() -> Self {
+    Self::NEG_INF
+  }
+  fn one() -> Self {
+    MaxPlus(0)
   }
-  let mut m = k;
-  let mut y = x.clone();
-  while m > 0 {
-    if 1 & m == 1 {
-      ret = matrix_product(&ret, &y);
+  fn add(l: Self, r: Self) -> Self {
+    l.max(r)
+  }
+  fn mul(l: Self, r: Self) -> Self {
+    if l == Self::NEG_INF || r == Self::NEG_INF {
+      Self::NEG_INF
+    } else {
+      MaxPlus(l.0 + r.0)
     }
-    y = matrix_product(&y, &y);
-    m /= 2;
   }
-  ret
 }
 
-struct Matrix {
-  a: Vec<Vec<f64>>, // 拡大係数行列
+/// (xor, and) 半環。ビットごとに GF(2) の行列積になる
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XorAnd(pub u64);
+
+impl Semiring for XorAnd {
+  fn zero() -> Self {
+    XorAnd(0)
+  }
+  fn one() -> Self {
+    XorAnd(!0)
+  }
+  fn add(l: Self, r: Self) -> Self {
+    XorAnd(l.0 ^ r.0)
+  }
+  fn mul(l: Self, r: Self) -> Self {
+    XorAnd(l.0 & r.0)
+  }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matrix<T> {
+  pub a: Vec<Vec<T>>,
 }
 
-struct MatrixInfo {
-  rank: usize,
+pub struct MatrixInfo {
+  pub rank: usize,
 }
 
-impl Matrix {
-  const EPS: f64 = 1e-10;
-  fn new(a: Vec<Vec<f64>>) -> Self {
+impl<T> Matrix<T> {
+  pub fn new(a: Vec<Vec<T>>) -> Self {
     Matrix { a }
   }
-  fn gauss_jordan(&mut self) -> MatrixInfo {
+  pub fn height(&self) -> usize {
+    self.a.len()
+  }
+  pub fn width(&self) -> usize {
+    self.a.first().map_or(0, |row| row.len())
+  }
+}
+
+impl<T: Semiring> Matrix<T> {
+  pub fn zeros(h: usize, w: usize) -> Self {
+    Matrix { a: vec![vec![T::zero(); w]; h] }
+  }
+  pub fn identity(n: usize) -> Self {
+    let mut ret = Self::zeros(n, n);
+    for i in 0..n {
+      ret.a[i][i] = T::one();
+    }
+    ret
+  }
+  pub fn transpose(&self) -> Self {
+    let (h, w) = (self.height(), self.width());
+    let mut ret = Self::zeros(w, h);
+    for i in 0..h {
+      for j in 0..w {
+        ret.a[j][i] = self.a[i][j];
+      }
+    }
+    ret
+  }
+  /// 正方行列の k 乗 O(n^3 log k)
+  pub fn pow(&self, k: u64) -> Self {
+    assert_eq!(self.height(), self.width());
+    let mut ret = Self::identity(self.height());
+    let mut y = self.clone();
+    let mut k = k;
+    while k > 0 {
+      if k & 1 == 1 {
+        ret = &ret * &y;
+      }
+      y = &y * &y;
+      k >>= 1;
+    }
+    ret
+  }
+  /// 行列 * 列ベクトル
+  pub fn mul_vec(&self, v: &[T]) -> Vec<T> {
+    assert_eq!(self.width(), v.len());
+    self
+      .a
+      .iter()
+      .map(|row| row.iter().zip(v).fold(T::zero(), |acc, (&x, &y)| T::add(acc, T::mul(x, y))))
+      .collect()
+  }
+}
+
+impl<T: Semiring> Mul for &Matrix<T> {
+  type Output = Matrix<T>;
+  fn mul(self, rhs: Self) -> Matrix<T> {
+    assert_eq!(self.width(), rhs.height());
+    let mut ret = Matrix::zeros(self.height(), rhs.width());
+    for i in 0..self.height() {
+      for j in 0..self.width() {
+        let x = self.a[i][j];
+        for k in 0..rhs.width() {
+          ret.a[i][k] = T::add(ret.a[i][k], T::mul(x, rhs.a[j][k]));
+        }
+      }
+    }
+    ret
+  }
+}
+
+impl<T: Semiring> Mul for Matrix<T> {
+  type Output = Matrix<T>;
+  fn mul(self, rhs: Self) -> Matrix<T> {
+    &self * &rhs
+  }
+}
+
+impl<T> Index<usize> for Matrix<T> {
+  type Output = Vec<T>;
+  fn index(&self, i: usize) -> &Vec<T> {
+    &self.a[i]
+  }
+}
+
+impl<T> IndexMut<usize> for Matrix<T> {
+  fn index_mut(&mut self, i: usize) -> &mut Vec<T> {
+    &mut self.a[i]
+  }
+}
+
+impl Matrix<f64> {
+  const EPS: f64 = 1e-10;
+  /// a を拡大係数行列とみて掃き出す
+  pub fn gauss_jordan(&mut self) -> MatrixInfo {
     let (m, n) = (self.a.len(), self.a[0].len());
     let mut rank = 0;
     for col in 0..n - 1 {
@@ -69,7 +258,7 @@ impl Matrix {
         self.a[rank][j] /= fac;
       }
       for row in (0..m).filter(|&x| x != rank) {
-        if self.a[row][col] > Self::EPS {
+        if self.a[row][col].abs() > Self::EPS {
           let fac = self.a[row][col];
           for j in 0..n {
             self.a[row][j] -= self.a[rank][j] * fac
@@ -82,6 +271,189 @@ impl Matrix {
   }
 }
 
+/// 素数 mod 上の線形代数
+impl<T: ModInt> Matrix<T> {
+  /// 先頭 cols 列について行簡約階段形にする
+  /// (各行のピボット列, 行列式に掛かる係数) を返す O(h * w * min(h, cols))
+  fn row_reduce(&mut self, cols: usize) -> (Vec<usize>, T) {
+    let (h, w) = (self.height(), self.width());
+    let mut pivots = vec![];
+    let mut coef = T::from(1);
+    for col in 0..cols {
+      let rank = pivots.len();
+      let Some(pivot) = (rank..h).find(|&row| self.a[row][col] != T::default()) else { continue; };
+      if pivot != rank {
+        self.a.swap(rank, pivot);
+        coef = -coef;
+      }
+      let p = self.a[rank][col];
+      coef *= p;
+      let p_inv = p.inv();
+      for j in col..w {
+        self.a[rank][j] *= p_inv;
+      }
+      for row in 0..h {
+        let fac = self.a[row][col];
+        if row == rank || fac == T::default() {
+          continue;
+        }
+        for j in col..w {
+          let x = self.a[rank][j];
+          self.a[row][j] -= fac * x;
+        }
+      }
+      pivots.push(col);
+    }
+    (pivots, coef)
+  }
+  pub fn rank(&self) -> usize {
+    self.clone().row_reduce(self.width()).0.len()
+  }
+  /// O(n^3)
+  pub fn det(&self) -> T {
+    let n = self.height();
+    assert_eq!(n, self.width());
+    let (pivots, coef) = self.clone().row_reduce(n);
+    if pivots.len() < n {
+      T::default()
+    } else {
+      coef
+    }
+  }
+  /// 正則でなければ None O(n^3)
+  pub fn inv(&self) -> Option<Self> {
+    let n = self.height();
+    assert_eq!(n, self.width());
+    let mut aug = Matrix::zeros(n, 2 * n);
+    for i in 0..n {
+      aug.a[i][..n].copy_from_slice(&self.a[i]);
+      aug.a[i][n + i] = T::from(1);
+    }
+    let (pivots, _) = aug.row_reduce(n);
+    if pivots.len() < n {
+      return None;
+    }
+    Some(Matrix::new(aug.a.into_iter().map(|row| row[n..].to_vec()).collect()))
+  }
+  /// Ax = b の解の一つと、Ax = 0 の解空間の基底を返す。解がなければ None
+  /// 解全体は x + Σ c_i * basis_i
+  pub fn solve(&self, b: &[T]) -> Option<(Vec<T>, Vec<Vec<T>>)> {
+    let (h, w) = (self.height(), self.width());
+    assert_eq!(h, b.len());
+    let mut aug = Matrix::zeros(h, w + 1);
+    for ((row, src), &bi) in aug.a.iter_mut().zip(&self.a).zip(b) {
+      row[..w].copy_from_slice(src);
+      row[w] = bi;
+    }
+    let (pivots, _) = aug.row_reduce(w);
+    if aug.a[pivots.len()..].iter().any(|row| row[w] != T::default()) {
+      return None;
+    }
+    let mut x = vec![T::default(); w];
+    for (i, &col) in pivots.iter().enumerate() {
+      x[col] = aug.a[i][w];
+    }
+    let mut is_pivot = vec![false; w];
+    for &col in &pivots {
+      is_pivot[col] = true;
+    }
+    let mut basis = vec![];
+    for free in (0..w).filter(|&j| !is_pivot[j]) {
+      let mut v = vec![T::default(); w];
+      v[free] = T::from(1);
+      for (i, &col) in pivots.iter().enumerate() {
+        v[col] = -aug.a[i][free];
+      }
+      basis.push(v);
+    }
+    Some((x, basis))
+  }
+  /// 特性多項式 det(xI - A) を係数の低い順に返す (長さ n + 1, モニック)
+  /// 相似変換で上 Hessenberg 行列にしてから漸化式で求める O(n^3)
+  pub fn char_poly(&self) -> Vec<T> {
+    let n = self.height();
+    assert_eq!(n, self.width());
+    let zero = T::default();
+    let mut a = self.a.clone();
+    for j in 0..n.saturating_sub(2) {
+      let Some(pivot) = (j + 1..n).find(|&i| a[i][j] != zero) else { continue; };
+      if pivot != j + 1 {
+        a.swap(pivot, j + 1);
+        for row in a.iter_mut() {
+          row.swap(pivot, j + 1);
+        }
+      }
+      let p_inv = a[j + 1][j].inv();
+      for k in j + 2..n {
+        let u = a[k][j] * p_inv;
+        if u == zero {
+          continue;
+        }
+        // 行 k -= u * 行 (j + 1), 列 (j + 1) += u * 列 k
+        let (top, bottom) = a.split_at_mut(k);
+        for (y, &x) in bottom[0].iter_mut().zip(&top[j + 1]) {
+          *y -= u * x;
+        }
+        for row in a.iter_mut() {
+          let x = row[k];
+          row[j + 1] += u * x;
+        }
+      }
+    }
+    // p[i] は左上 i x i 部分の特性多項式
+    let mut p = vec![vec![T::from(1)]];
+    for i in 0..n {
+      let mut next = vec![zero; i + 2];
+      for (k, &x) in p[i].iter().enumerate() {
+        next[k + 1] += x;
+        next[k] -= a[i][i] * x;
+      }
+      let mut beta = T::from(1);
+      for j in (0..i).rev() {
+        beta *= a[j + 1][j];
+        let coef = -(a[j][i] * beta);
+        for (k, &x) in p[j].iter().enumerate() {
+          next[k] += coef * x;
+        }
+      }
+      p.push(next);
+    }
+    p.pop().unwrap()
+  }
+}
+
+/// 任意の mod (素数でなくてよい) での行列式
+/// 行同士のユークリッドの互除法で掃き出す O(n^2 (n + log m))
+pub fn det_mod(a: &[Vec<i64>], m: u64) -> u64 {
+  let n = a.len();
+  let mut a = a
+    .iter()
+    .map(|row| {
+      assert_eq!(row.len(), n);
+      row.iter().map(|&x| x.rem_euclid(m as i64) as u64).collect::<Vec<_>>()
+    })
+    .collect::<Vec<_>>();
+  let mul = |x: u64, y: u64| (x as u128 * y as u128 % m as u128) as u64;
+  let mut det = 1 % m;
+  for i in 0..n {
+    for j in i + 1..n {
+      while a[j][i] != 0 {
+        let q = a[i][i] / a[j][i];
+        if q != 0 {
+          let (top, bottom) = a.split_at_mut(j);
+          for (x, &y) in top[i][i..].iter_mut().zip(&bottom[0][i..]) {
+            *x = (*x + m - mul(q, y)) % m;
+          }
+        }
+        a.swap(i, j);
+        det = (m - det) % m;
+      }
+    }
+    det = mul(det, a[i][i]);
+  }
+  det
+}
+
 struct MatrixXOR {
   a: Vec<Vec<usize>>, // 拡大係数行列 && 0,1 only
 }
@@ -143,3 +515,142 @@ impl MatrixBitSet {
     MatrixInfo { rank }
   }
 }
+
+#[test]
+fn test_semiring_matrix() {
+  use super::modint::Mod;
+  // フィボナッチ
+  let fib = Matrix::new(vec![vec![Mod::new(1), Mod::new(1)], vec![Mod::new(1), Mod::new(0)]]);
+  assert_eq!(fib.pow(10)[0][1], Mod::new(55));
+  assert_eq!(fib.pow(0), Matrix::identity(2));
+  assert_eq!(fib.pow(100)[0][1], Mod::new(494958974));
+  let a = Matrix::new(vec![vec![Mod::new(1), Mod::new(2), Mod::new(3)], vec![Mod::new(4), Mod::new(5), Mod::new(6)]]);
+  let b = Matrix::new(vec![vec![Mod::new(1)], vec![Mod::new(0)], vec![Mod::new(-1)]]);
+  assert_eq!((&a * &b).a, vec![vec![Mod::new(-2)], vec![Mod::new(-2)]]);
+  assert_eq!(a.mul_vec(&[Mod::new(1), Mod::new(0), Mod::new(-1)]), vec![Mod::new(-2), Mod::new(-2)]);
+  assert_eq!(a.transpose().transpose(), a);
+  assert_eq!(a.transpose().height(), 3);
+
+  // 辺をちょうど k 本使う最短路・最長路
+  let inf = MinPlus::INF;
+  let g = Matrix::new(vec![
+    vec![inf, MinPlus(1), MinPlus(5)],
+    vec![inf, inf, MinPlus(2)],
+    vec![MinPlus(1), inf, inf],
+  ]);
+  assert_eq!(g.pow(2)[0][2], MinPlus(3));
+  assert_eq!(g.pow(3)[0][0], MinPlus(4));
+  assert_eq!(g.pow(1)[1][0], inf);
+  let ninf = MaxPlus::NEG_INF;
+  let g = Matrix::new(vec![
+    vec![ninf, MaxPlus(1), MaxPlus(5)],
+    vec![ninf, ninf, MaxPlus(2)],
+    vec![MaxPlus(1), ninf, ninf],
+  ]);
+  assert_eq!(g.pow(2)[0][0], MaxPlus(6));
+  assert_eq!(g.pow(3)[0][0], MaxPlus(4));
+
+  // 到達可能性
+  let g = Matrix::new(vec![vec![false, true, false], vec![false, false, true], vec![false, false, false]]);
+  assert!(g.pow(2)[0][2]);
+  assert!(!g.pow(3)[0][2]);
+
+  // (xor, and) での線形漸化式 a_{n+2} = a_{n+1} & c1 ^ a_n & c0
+  let (c0, c1) = (0b1100u64, 0b1010u64);
+  let mut seq = vec![0b0110u64, 0b0011];
+  for i in 2..20 {
+    seq.push((seq[i - 1] & c1) ^ (seq[i - 2] & c0));
+  }
+  let m = Matrix::new(vec![vec![XorAnd(c1), XorAnd(c0)], vec![XorAnd::one(), XorAnd::zero()]]);
+  let v = m.pow(18).mul_vec(&[XorAnd(seq[1]), XorAnd(seq[0])]);
+  assert_eq!(v[0], XorAnd(seq[19]));
+
+  let mut lin = Matrix::new(vec![vec![2.0, 1.0, 5.0], vec![1.0, -1.0, 1.0]]);
+  assert_eq!(lin.gauss_jordan().rank, 2);
+  assert!((lin[0][2] - 2.0).abs() < 1e-9 && (lin[1][2] - 1.0).abs() < 1e-9);
+  // ピボット列の負の係数も消去される
+  let mut lin = Matrix::new(vec![vec![1.0, 1.0, 3.0], vec![-1.0, 1.0, 1.0]]);
+  assert_eq!(lin.gauss_jordan().rank, 2);
+  assert!((lin[0][2] - 1.0).abs() < 1e-9 && (lin[1][2] - 2.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_modint_linear_algebra() {
+  use super::modint::Mod;
+  let to_mat = |a: &[&[i64]]| Matrix::new(a.iter().map(|row| row.iter().map(|&x| Mod::new(x)).collect()).collect());
+  let a = to_mat(&[&[2, 1, 1], &[1, 3, 2], &[1, 0, 0]]);
+  assert_eq!(a.det(), Mod::new(-1));
+  assert_eq!(a.rank(), 3);
+  let inv = a.inv().unwrap();
+  assert_eq!(&a * &inv, Matrix::identity(3));
+  let singular = to_mat(&[&[1, 2, 3], &[2, 4, 6], &[1, 0, 1]]);
+  assert_eq!(singular.det(), Mod::new(0));
+  assert_eq!(singular.rank(), 2);
+  assert!(singular.inv().is_none());
+
+  // 解が一意でない連立方程式
+  let b = [Mod::new(4), Mod::new(8), Mod::new(2)];
+  let (x, basis) = singular.solve(&b).unwrap();
+  assert_eq!(singular.mul_vec(&x), b.to_vec());
+  assert_eq!(basis.len(), 1);
+  let zero = vec![Mod::new(0); 3];
+  assert_eq!(singular.mul_vec(&basis[0]), zero);
+  assert!(singular.solve(&[Mod::new(4), Mod::new(7), Mod::new(2)]).is_none());
+  let wide = to_mat(&[&[1, 1, 1, 1]]);
+  let (x, basis) = wide.solve(&[Mod::new(3)]).unwrap();
+  assert_eq!(wide.mul_vec(&x), vec![Mod::new(3)]);
+  assert_eq!(basis.len(), 3);
+  assert_eq!(Matrix::new(basis).rank(), 3);
+
+  // 行列木定理: K_5 の全域木は 5^3 = 125 個
+  let n = 5;
+  let lap = Matrix::new(
+    (1..n)
+      .map(|i| (1..n).map(|j| if i == j { Mod::new(n as i64 - 1) } else { Mod::new(-1) }).collect())
+      .collect(),
+  );
+  assert_eq!(lap.det(), Mod::new(125));
+
+  let mut rng = crate::xorshift::Xorshift::new();
+  for _ in 0..200 {
+    let n = rng.rand(5) as usize + 1;
+    let m = rng.rand(100) + 1;
+    let a = (0..n)
+      .map(|_| (0..n).map(|_| rng.rand(41) as i64 - 20).collect::<Vec<_>>())
+      .collect::<Vec<_>>();
+    let det = Matrix::new(a.iter().map(|row| row.iter().map(|&x| Mod::new(x)).collect()).collect()).det();
+    // 成分が小さいので |det| < 998244353 / 2
+    let exact = if det.val() > 998244353 / 2 { det.val() as i64 - 998244353 } else { det.val() as i64 };
+    assert_eq!(det_mod(&a, m), exact.rem_euclid(m as i64) as u64);
+  }
+}
+
+#[test]
+fn test_char_poly() {
+  use super::modint::Mod;
+  let a = Matrix::new(vec![vec![Mod::new(1), Mod::new(2)], vec![Mod::new(3), Mod::new(4)]]);
+  assert_eq!(a.char_poly(), vec![Mod::new(-2), Mod::new(-5), Mod::new(1)]);
+  assert_eq!(Matrix::<Mod>::zeros(0, 0).char_poly(), vec![Mod::new(1)]);
+
+  let mut rng = crate::xorshift::Xorshift::new();
+  for n in 1..8 {
+    // 疎な行列でピボット交換も通す
+    let a = Matrix::new(
+      (0..n)
+        .map(|_| (0..n).map(|_| if rng.rand(3) == 0 { Mod::new(rng.rand(10) as i64) } else { Mod::new(0) }).collect())
+        .collect::<Vec<Vec<_>>>(),
+    );
+    let p = a.char_poly();
+    assert_eq!(p.len(), n + 1);
+    for x in 0..5 {
+      let mut xi_a = Matrix::<Mod>::identity(n);
+      for i in 0..n {
+        for j in 0..n {
+          xi_a[i][j] = xi_a[i][j] * Mod::new(x) - a[i][j];
+        }
+      }
+      let val = p.iter().rev().fold(Mod::new(0), |acc, &c| acc * Mod::new(x) + c);
+      assert_eq!(xi_a.det(), val);
+    }
+  }
+}
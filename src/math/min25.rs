@@ -0,0 +1,72 @@
+use cargo_snippet::snippet;
+
+use super::modint::ModInt;
+use super::prime::{isqrt, LinearSieve};
+use super::prime_count::{prime_sum, PrimeSumTable};
+
+/// min_25 篩
+/// 乗法的関数 f について Σ_{1 <= i <= n} f(i) を求める
+/// f(p) = Σ coef[k] p^k (p: 素数), f_pe(p, e) = f(p^e)
+/// 計算量: O(n^(3/4) / log n)
+#[snippet(name = "min25_sieve", include = "prime_count")]
+pub fn min25_sieve<T: ModInt>(n: u64, coef: &[T], f_pe: impl Fn(u64, u32) -> T) -> T {
+  if n == 0 {
+    return T::default();
+  }
+  let g = prime_sum(n, coef);
+  let primes = LinearSieve::new(isqrt(n) as usize).primes().iter().map(|&p| p as u64).collect::<Vec<_>>();
+  T::from(1) + min25_rec(n, 0, &primes, &g, &f_pe)
+}
+
+/// Σ_{2 <= i <= v, i の最小素因数 >= primes[j]} f(i)
+#[snippet(name = "min25_sieve", include = "prime_count")]
+fn min25_rec<T: ModInt>(v: u64, j: usize, primes: &[u64], g: &PrimeSumTable<T>, f_pe: &impl Fn(u64, u32) -> T) -> T {
+  let below = if j == 0 { T::default() } else { g.get(primes[j - 1]) };
+  if j < primes.len() && primes[j] > v {
+    return T::default();
+  }
+  let mut res = g.get(v) - below;
+  for (k, &p) in primes.iter().enumerate().skip(j).take_while(|&(_, &p)| p * p <= v) {
+    let mut pe = p;
+    let mut e = 1;
+    while pe * p <= v {
+      res += f_pe(p, e) * min25_rec(v / pe, k + 1, primes, g, f_pe) + f_pe(p, e + 1);
+      pe *= p;
+      e += 1;
+    }
+  }
+  res
+}
+
+#[test]
+fn test_min25_sieve() {
+  use crate::math::modint::Mod;
+  let n = 3000;
+  let ls = LinearSieve::new(n);
+  let d = ls.divisor_count();
+  let sigma = ls.divisor_sum();
+  let phi = ls.euler_phi();
+  let mu = ls.mobius();
+  let m = |p: u64| Mod::new((p % 998244353) as i64);
+  for x in (0..=n as u64).step_by(7).chain([1, 2, 3, 4, 1000]) {
+    let xs = 1..=x as usize;
+    let expected = Mod::new(xs.clone().map(|i| d[i] as i64).sum::<i64>());
+    assert_eq!(min25_sieve(x, &[Mod::new(2)], |_, e| Mod::new(e as i64 + 1)), expected);
+
+    let expected = Mod::new(xs.clone().map(|i| sigma[i] as i64).sum::<i64>());
+    let f = |p: u64, e: u32| (m(p).pow(e as u64 + 1) - 1) / (m(p) - 1);
+    assert_eq!(min25_sieve(x, &[Mod::new(1), Mod::new(1)], f), expected);
+
+    let expected = Mod::new(xs.clone().map(|i| phi[i] as i64).sum::<i64>());
+    let f = |p: u64, e: u32| m(p).pow(e as u64 - 1) * (m(p) - 1);
+    assert_eq!(min25_sieve(x, &[Mod::new(-1), Mod::new(1)], f), expected);
+
+    let expected = Mod::new(xs.clone().map(|i| mu[i]).sum::<i64>());
+    let f = |_, e| if e == 1 { Mod::new(-1) } else { Mod::new(0) };
+    assert_eq!(min25_sieve(x, &[Mod::new(-1)], f), expected);
+  }
+  // Σ d(i) = Σ floor(n / i)
+  let n = 10_000_000u64;
+  let expected = (1..=n).map(|i| Mod::new((n / i) as i64)).sum::<Mod>();
+  assert_eq!(min25_sieve(n, &[Mod::new(2)], |_, e| Mod::new(e as i64 + 1)), expected);
+}
@@ -1,10 +1,18 @@
+pub mod bigint;
 pub mod combination;
 pub mod crt;
+pub mod floor_sum;
 pub mod gcd;
 pub mod hilbert;
 pub mod matrix;
+pub mod min25;
 pub mod modint;
 pub mod modulo;
+pub mod montgomery;
 pub mod prime;
+pub mod prime_count;
 pub mod rational;
+pub mod special_numbers;
+pub mod sqrt_mod;
 pub mod symmetric_group;
+pub mod xor_basis;
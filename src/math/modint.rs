@@ -1,88 +1,449 @@
+use cargo_snippet::snippet;
+use std::iter::{Product, Sum};
+use std::ops::*;
 
+use super::modulo::checked_inv_mod;
+use super::sqrt_mod::sqrt_mod;
 
-
-const M: i64 = 998244353;
-#[derive(Debug, Clone, Copy, Default, PartialOrd, Ord, PartialEq, Eq)]
-pub struct Mod(pub i64);
-impl ::std::fmt::Display for Mod {
-  fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
-    write!(f, "{}", self.0)
-  }
-}
-impl Mod {
-  pub fn new(v: i64) -> Mod {
-    Mod((v + M) % M)
-  }
-  pub fn pow(self, r: i64) -> Mod {
+/// 法 M の剰余環の元として振る舞う型の共通インターフェース
+#[snippet("modint")]
+pub trait ModInt:
+  Copy
+  + Default
+  + Eq
+  + ::std::fmt::Debug
+  + ::std::fmt::Display
+  + Add<Output = Self>
+  + Sub<Output = Self>
+  + Mul<Output = Self>
+  + Div<Output = Self>
+  + AddAssign
+  + SubAssign
+  + MulAssign
+  + DivAssign
+  + Neg<Output = Self>
+  + Sum
+  + Product
+  + From<i64>
+{
+  fn modulus() -> u32;
+  /// 0 <= v < modulus() を仮定して剰余を取らずに作る
+  fn raw(v: u32) -> Self;
+  fn val(self) -> u32;
+  fn inv(self) -> Self;
+  fn pow(self, r: u64) -> Self {
     let mut r = r;
     let mut k = self;
-    let mut ret = 1.into();
+    let mut ret = Self::raw(1 % Self::modulus());
     while r > 0 {
-      if r % 2 != 0 {
-        ret = ret * k;
+      if r & 1 == 1 {
+        ret *= k;
       }
       r /= 2;
-      k = k * k;
+      k *= k;
     }
     ret
   }
-  pub fn inv(self) -> Mod {
-    self.pow(M - 2)
+}
+
+#[snippet("modint")]
+#[derive(Debug, Clone, Copy, Default, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct StaticModInt<const M: u32>(u32);
+#[snippet("modint")]
+pub type ModInt998244353 = StaticModInt<998244353>;
+#[snippet("modint")]
+pub type ModInt1000000007 = StaticModInt<1000000007>;
+#[snippet("modint")]
+pub type Mod = ModInt998244353;
+
+#[snippet("modint")]
+impl<const M: u32> ::std::fmt::Display for StaticModInt<M> {
+  fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+    write!(f, "{}", self.0)
   }
 }
-use std::ops::*;
-impl<T: Into<Mod>> Add<T> for Mod {
-  type Output = Mod;
+#[snippet("modint")]
+impl<const M: u32> StaticModInt<M> {
+  pub fn new(v: i64) -> Self {
+    Self(v.rem_euclid(M as i64) as u32)
+  }
+  pub fn raw(v: u32) -> Self {
+    Self(v)
+  }
+  pub fn val(self) -> u32 {
+    self.0
+  }
+  pub fn pow(self, r: u64) -> Self {
+    <Self as ModInt>::pow(self, r)
+  }
+  /// M が素数であることを仮定する
+  pub fn inv(self) -> Self {
+    assert_ne!(self.0, 0, "0 has no inverse");
+    self.pow(M as u64 - 2)
+  }
+}
+#[snippet(name = "modint_sqrt", include = "modint, sqrt_mod")]
+impl<const M: u32> StaticModInt<M> {
+  /// M が素数であることを仮定する
+  pub fn sqrt(self) -> Option<Self> {
+    sqrt_mod(self.0 as u64, M as u64).map(|x| Self(x as u32))
+  }
+}
+#[snippet("modint")]
+impl<const M: u32> ModInt for StaticModInt<M> {
+  fn modulus() -> u32 {
+    M
+  }
+  fn raw(v: u32) -> Self {
+    Self(v)
+  }
+  fn val(self) -> u32 {
+    self.0
+  }
+  fn inv(self) -> Self {
+    StaticModInt::inv(self)
+  }
+}
+#[snippet("modint")]
+impl<const M: u32, T: Into<StaticModInt<M>>> Add<T> for StaticModInt<M> {
+  type Output = Self;
+  fn add(self, rhs: T) -> Self::Output {
+    let v = self.0 as u64 + rhs.into().0 as u64;
+    Self(if v >= M as u64 { v - M as u64 } else { v } as u32)
+  }
+}
+#[snippet("modint")]
+impl<const M: u32, T: Into<StaticModInt<M>>> AddAssign<T> for StaticModInt<M> {
+  fn add_assign(&mut self, rhs: T) {
+    *self = *self + rhs;
+  }
+}
+#[snippet("modint")]
+impl<const M: u32, T: Into<StaticModInt<M>>> Sub<T> for StaticModInt<M> {
+  type Output = Self;
+  fn sub(self, rhs: T) -> Self::Output {
+    let v = self.0 as u64 + M as u64 - rhs.into().0 as u64;
+    Self(if v >= M as u64 { v - M as u64 } else { v } as u32)
+  }
+}
+#[snippet("modint")]
+impl<const M: u32, T: Into<StaticModInt<M>>> SubAssign<T> for StaticModInt<M> {
+  fn sub_assign(&mut self, rhs: T) {
+    *self = *self - rhs;
+  }
+}
+#[snippet("modint")]
+impl<const M: u32, T: Into<StaticModInt<M>>> Mul<T> for StaticModInt<M> {
+  type Output = Self;
+  fn mul(self, rhs: T) -> Self::Output {
+    Self((self.0 as u64 * rhs.into().0 as u64 % M as u64) as u32)
+  }
+}
+#[snippet("modint")]
+impl<const M: u32, T: Into<StaticModInt<M>>> MulAssign<T> for StaticModInt<M> {
+  fn mul_assign(&mut self, rhs: T) {
+    *self = *self * rhs;
+  }
+}
+#[snippet("modint")]
+impl<const M: u32, T: Into<StaticModInt<M>>> Div<T> for StaticModInt<M> {
+  type Output = Self;
+  #[allow(clippy::suspicious_arithmetic_impl)]
+  fn div(self, rhs: T) -> Self::Output {
+    self * rhs.into().inv()
+  }
+}
+#[snippet("modint")]
+impl<const M: u32, T: Into<StaticModInt<M>>> DivAssign<T> for StaticModInt<M> {
+  fn div_assign(&mut self, rhs: T) {
+    *self = *self / rhs;
+  }
+}
+#[snippet("modint")]
+impl<const M: u32> Neg for StaticModInt<M> {
+  type Output = Self;
+  fn neg(self) -> Self::Output {
+    Self(0) - self
+  }
+}
+#[snippet("modint")]
+impl<const M: u32, T: Into<i64>> From<T> for StaticModInt<M> {
+  fn from(v: T) -> Self {
+    Self::new(v.into())
+  }
+}
+#[snippet("modint")]
+impl<const M: u32> Sum for StaticModInt<M> {
+  fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+    iter.fold(Self(0), |acc, x| acc + x)
+  }
+}
+#[snippet("modint")]
+impl<'a, const M: u32> Sum<&'a Self> for StaticModInt<M> {
+  fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+    iter.copied().sum()
+  }
+}
+#[snippet("modint")]
+impl<const M: u32> Product for StaticModInt<M> {
+  fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+    iter.fold(Self::new(1), |acc, x| acc * x)
+  }
+}
+#[snippet("modint")]
+impl<'a, const M: u32> Product<&'a Self> for StaticModInt<M> {
+  fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+    iter.copied().product()
+  }
+}
+
+/// 除算を乗算とシフトに置き換えて a * b mod m を計算する
+/// 1 <= m < 2^31
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+#[derive(Debug, Clone, Copy)]
+pub struct Barrett {
+  m: u32,
+  im: u64,
+}
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl Barrett {
+  pub fn new(m: u32) -> Self {
+    assert!((1..1 << 31).contains(&m));
+    Barrett {
+      m,
+      im: (u64::MAX / m as u64).wrapping_add(1),
+    }
+  }
+  pub fn umod(&self) -> u32 {
+    self.m
+  }
+  /// a, b < m
+  pub fn mul(&self, a: u32, b: u32) -> u32 {
+    let z = a as u64 * b as u64;
+    let x = ((z as u128 * self.im as u128) >> 64) as u64;
+    let v = z.wrapping_sub(x.wrapping_mul(self.m as u64)) as u32;
+    if self.m <= v {
+      v.wrapping_add(self.m)
+    } else {
+      v
+    }
+  }
+}
+
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+thread_local! {
+  static DYNAMIC_MOD_BARRETT: ::std::cell::Cell<Barrett> = ::std::cell::Cell::new(Barrett::new(998244353));
+}
+
+/// 実行時に法を決める modint
+/// 法はスレッドごとに共有され、set_modulus で切り替える (既定は 998244353, 法は 2^31 未満)
+/// 法は素数でなくてもよい
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+#[derive(Debug, Clone, Copy, Default, PartialOrd, Ord, PartialEq, Eq, Hash)]
+pub struct DynamicModInt(u32);
+
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl ::std::fmt::Display for DynamicModInt {
+  fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+    write!(f, "{}", self.0)
+  }
+}
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl DynamicModInt {
+  pub fn set_modulus(m: u32) {
+    DYNAMIC_MOD_BARRETT.with(|b| b.set(Barrett::new(m)));
+  }
+  pub fn modulus() -> u32 {
+    Self::barrett().umod()
+  }
+  fn barrett() -> Barrett {
+    DYNAMIC_MOD_BARRETT.with(|b| b.get())
+  }
+  pub fn new(v: i64) -> Self {
+    Self(v.rem_euclid(Self::modulus() as i64) as u32)
+  }
+  pub fn raw(v: u32) -> Self {
+    Self(v)
+  }
+  pub fn val(self) -> u32 {
+    self.0
+  }
+  pub fn pow(self, r: u64) -> Self {
+    <Self as ModInt>::pow(self, r)
+  }
+  /// 拡張ユークリッドで逆元を求める。gcd(x, m) != 1 なら None
+  pub fn checked_inv(self) -> Option<Self> {
+    checked_inv_mod(self.0 as u64, Self::modulus() as u64).map(|x| Self(x as u32))
+  }
+  pub fn inv(self) -> Self {
+    self.checked_inv().expect("not invertible")
+  }
+}
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl ModInt for DynamicModInt {
+  fn modulus() -> u32 {
+    DynamicModInt::modulus()
+  }
+  fn raw(v: u32) -> Self {
+    Self(v)
+  }
+  fn val(self) -> u32 {
+    self.0
+  }
+  fn inv(self) -> Self {
+    DynamicModInt::inv(self)
+  }
+}
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<T: Into<DynamicModInt>> Add<T> for DynamicModInt {
+  type Output = Self;
   fn add(self, rhs: T) -> Self::Output {
-    Mod::new(self.0 + rhs.into().0)
+    let m = Self::modulus() as u64;
+    let v = self.0 as u64 + rhs.into().0 as u64;
+    Self(if v >= m { v - m } else { v } as u32)
   }
 }
-impl<T: Into<Mod>> AddAssign<T> for Mod {
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<T: Into<DynamicModInt>> AddAssign<T> for DynamicModInt {
   fn add_assign(&mut self, rhs: T) {
     *self = *self + rhs;
   }
 }
-impl<T: Into<Mod>> Sub<T> for Mod {
-  type Output = Mod;
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<T: Into<DynamicModInt>> Sub<T> for DynamicModInt {
+  type Output = Self;
   fn sub(self, rhs: T) -> Self::Output {
-    Mod::new(self.0 - rhs.into().0 + M)
+    let m = Self::modulus() as u64;
+    let v = self.0 as u64 + m - rhs.into().0 as u64;
+    Self(if v >= m { v - m } else { v } as u32)
   }
 }
-impl<T: Into<Mod>> SubAssign<T> for Mod {
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<T: Into<DynamicModInt>> SubAssign<T> for DynamicModInt {
   fn sub_assign(&mut self, rhs: T) {
     *self = *self - rhs;
   }
 }
-impl<T: Into<Mod>> Mul<T> for Mod {
-  type Output = Mod;
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<T: Into<DynamicModInt>> Mul<T> for DynamicModInt {
+  type Output = Self;
   fn mul(self, rhs: T) -> Self::Output {
-    Mod::new(self.0 * rhs.into().0)
+    Self(Self::barrett().mul(self.0, rhs.into().0))
   }
 }
-impl<T: Into<Mod>> MulAssign<T> for Mod {
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<T: Into<DynamicModInt>> MulAssign<T> for DynamicModInt {
   fn mul_assign(&mut self, rhs: T) {
     *self = *self * rhs;
   }
 }
-impl<T: Into<Mod>> Div<T> for Mod {
-  type Output = Mod;
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<T: Into<DynamicModInt>> Div<T> for DynamicModInt {
+  type Output = Self;
+  #[allow(clippy::suspicious_arithmetic_impl)]
   fn div(self, rhs: T) -> Self::Output {
     self * rhs.into().inv()
   }
 }
-impl<T: Into<Mod>> DivAssign<T> for Mod {
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<T: Into<DynamicModInt>> DivAssign<T> for DynamicModInt {
   fn div_assign(&mut self, rhs: T) {
     *self = *self / rhs;
   }
 }
-impl Neg for Mod {
-  type Output = Mod;
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl Neg for DynamicModInt {
+  type Output = Self;
   fn neg(self) -> Self::Output {
-    Mod(0) - self
+    Self(0) - self
   }
 }
-impl<T: std::convert::Into<i64>> std::convert::From<T> for Mod {
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<T: Into<i64>> From<T> for DynamicModInt {
   fn from(v: T) -> Self {
-    Mod::new(v.into())
+    Self::new(v.into())
+  }
+}
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl Sum for DynamicModInt {
+  fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+    iter.fold(Self(0), |acc, x| acc + x)
+  }
+}
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<'a> Sum<&'a Self> for DynamicModInt {
+  fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+    iter.copied().sum()
+  }
+}
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl Product for DynamicModInt {
+  fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+    iter.fold(Self::new(1), |acc, x| acc * x)
+  }
+}
+#[snippet(name = "dynamic_modint", include = "modint, mod_u64")]
+impl<'a> Product<&'a Self> for DynamicModInt {
+  fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+    iter.copied().product()
   }
 }
+
+#[test]
+fn test_static_modint() {
+  type M7 = ModInt1000000007;
+  let a = M7::new(-1);
+  assert_eq!(a.val(), 1_000_000_006);
+  assert_eq!((a + 2).val(), 1);
+  assert_eq!((M7::new(3) - 5).val(), 1_000_000_005);
+  assert_eq!((a * a).val(), 1);
+  assert_eq!((M7::new(1) / 2 * 2).val(), 1);
+  assert_eq!(M7::new(2).pow(30).val(), 73741817);
+  assert_eq!((-M7::new(1)).val(), 1_000_000_006);
+
+  let b = Mod::new(3);
+  assert_eq!(b.inv() * b, Mod::new(1));
+  assert_eq!(format!("{}", Mod::new(-1)), "998244352");
+
+  let v = (1..=10).map(Mod::from).collect::<Vec<_>>();
+  assert_eq!(v.iter().sum::<Mod>(), Mod::new(55));
+  assert_eq!(v.iter().product::<Mod>(), Mod::new(3628800));
+  assert_eq!(v.into_iter().map(|x| x * x).sum::<Mod>(), Mod::new(385));
+
+  let c = Mod::new(2).sqrt().unwrap();
+  assert_eq!(c * c, Mod::new(2));
+  assert_eq!(Mod::new(3).sqrt(), None);
+  assert_eq!(M7::new(0).sqrt(), Some(M7::new(0)));
+}
+
+#[test]
+fn test_barrett() {
+  let mut rng = crate::xorshift::Xorshift::new();
+  for m in [1u32, 2, 3, 7, 998244353, 1_000_000_007, (1 << 31) - 2, (1 << 31) - 1] {
+    let b = Barrett::new(m);
+    for _ in 0..1000 {
+      let x = rng.rand(m as u64) as u32;
+      let y = rng.rand(m as u64) as u32;
+      assert_eq!(b.mul(x, y) as u64, x as u64 * y as u64 % m as u64);
+    }
+  }
+}
+
+#[test]
+fn test_dynamic_modint() {
+  DynamicModInt::set_modulus(12);
+  let a = DynamicModInt::new(-1);
+  assert_eq!(a.val(), 11);
+  assert_eq!((a + 5).val(), 4);
+  assert_eq!((a * 7).val(), 5);
+  assert_eq!(DynamicModInt::new(5).checked_inv(), Some(DynamicModInt::new(5)));
+  assert_eq!(DynamicModInt::new(4).checked_inv(), None);
+  assert_eq!((DynamicModInt::new(1) / 7 * 7).val(), 1);
+  assert_eq!(DynamicModInt::new(5).pow(3).val(), 5);
+
+  DynamicModInt::set_modulus(1_000_000_007);
+  let b = DynamicModInt::new(2).pow(30);
+  assert_eq!(b.val(), 73741817);
+  assert_eq!((1..=10).map(DynamicModInt::from).product::<DynamicModInt>().val(), 3628800);
+  assert_eq!(b / b, DynamicModInt::new(1));
+}
@@ -1,3 +1,5 @@
+use cargo_snippet::snippet;
+
 pub fn modinv(mut a: i64, modulo: i64) -> i64 {
     let mut b = modulo;
     let mut u = 1;
@@ -15,3 +17,59 @@ pub fn modinv(mut a: i64, modulo: i64) -> i64 {
     }
     u
 }
+
+/// a * b mod m
+#[snippet("mod_u64")]
+pub fn mul_mod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+/// a^e mod m
+#[snippet("mod_u64")]
+pub fn pow_mod(a: u64, e: u64, m: u64) -> u64 {
+    let mut ret = 1 % m;
+    let mut a = a % m;
+    let mut e = e;
+    while e > 0 {
+        if e & 1 == 1 {
+            ret = mul_mod(ret, a, m);
+        }
+        a = mul_mod(a, a, m);
+        e >>= 1;
+    }
+    ret
+}
+
+/// 拡張ユークリッドで a の mod m での逆元を求める。gcd(a, m) != 1 なら None
+#[snippet("mod_u64")]
+pub fn checked_inv_mod(a: u64, m: u64) -> Option<u64> {
+    let (mut a, mut b) = (a as i128, m as i128);
+    let (mut u, mut v) = (1i128, 0i128);
+    while b > 0 {
+        let t = a / b;
+        a -= t * b;
+        std::mem::swap(&mut a, &mut b);
+        u -= t * v;
+        std::mem::swap(&mut u, &mut v);
+    }
+    if a != 1 {
+        return None;
+    }
+    Some(u.rem_euclid(m as i128) as u64)
+}
+
+/// gcd(a, m) = 1 を仮定
+#[snippet("mod_u64")]
+pub fn inv_mod(a: u64, m: u64) -> u64 {
+    checked_inv_mod(a, m).expect("not invertible")
+}
+
+#[test]
+fn test_mod_u64() {
+    let m = (1 << 63) + 29;
+    assert_eq!(mul_mod(m - 1, m - 1, m), 1);
+    assert_eq!(pow_mod(3, m - 1, m), 1);
+    assert_eq!(mul_mod(inv_mod(12345, m), 12345, m), 1);
+    assert_eq!(checked_inv_mod(6, 9), None);
+    assert_eq!(checked_inv_mod(0, 1), Some(0));
+}
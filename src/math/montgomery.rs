@@ -0,0 +1,123 @@
+use cargo_snippet::snippet;
+
+use super::modulo::checked_inv_mod;
+
+/// 奇数 n (n < 2^62) を法とする 64bit モンゴメリ乗算
+/// 値は Montgomery 表現 (x * 2^64 mod n) で持つ
+#[snippet(name = "Montgomery64", include = "mod_u64")]
+#[derive(Debug, Clone, Copy)]
+pub struct Montgomery64 {
+  n: u64,
+  /// n * n_inv ≡ -1 (mod 2^64)
+  n_inv: u64,
+  /// 2^128 mod n
+  r2: u64,
+}
+
+#[snippet(name = "Montgomery64", include = "mod_u64")]
+impl Montgomery64 {
+  pub fn new(n: u64) -> Self {
+    assert!(n % 2 == 1 && n < 1 << 62);
+    // ニュートン法で n^-1 mod 2^64
+    let mut inv = n;
+    for _ in 0..5 {
+      inv = inv.wrapping_mul(2u64.wrapping_sub(n.wrapping_mul(inv)));
+    }
+    let r2 = ((n as u128).wrapping_neg() % n as u128) as u64;
+    Montgomery64 { n, n_inv: inv.wrapping_neg(), r2 }
+  }
+  pub fn modulus(&self) -> u64 {
+    self.n
+  }
+  /// t < n * 2^64 に対して t * 2^-64 mod n
+  fn reduce(&self, t: u128) -> u64 {
+    let m = (t as u64).wrapping_mul(self.n_inv);
+    let t = ((t + m as u128 * self.n as u128) >> 64) as u64;
+    if t >= self.n {
+      t - self.n
+    } else {
+      t
+    }
+  }
+  pub fn to_mont(&self, x: u64) -> u64 {
+    self.reduce((x % self.n) as u128 * self.r2 as u128)
+  }
+  pub fn from_mont(&self, x: u64) -> u64 {
+    self.reduce(x as u128)
+  }
+  /// Montgomery 表現の 1
+  pub fn one(&self) -> u64 {
+    self.to_mont(1)
+  }
+  pub fn add(&self, a: u64, b: u64) -> u64 {
+    let c = a + b;
+    if c >= self.n {
+      c - self.n
+    } else {
+      c
+    }
+  }
+  pub fn sub(&self, a: u64, b: u64) -> u64 {
+    if a >= b {
+      a - b
+    } else {
+      a + self.n - b
+    }
+  }
+  pub fn mul(&self, a: u64, b: u64) -> u64 {
+    self.reduce(a as u128 * b as u128)
+  }
+  /// O(log e)
+  pub fn pow(&self, a: u64, e: u64) -> u64 {
+    let mut ret = self.one();
+    let mut a = a;
+    let mut e = e;
+    while e > 0 {
+      if e & 1 == 1 {
+        ret = self.mul(ret, a);
+      }
+      a = self.mul(a, a);
+      e >>= 1;
+    }
+    ret
+  }
+  /// gcd(a, n) != 1 なら None
+  pub fn inv(&self, a: u64) -> Option<u64> {
+    checked_inv_mod(self.from_mont(a), self.n).map(|x| self.to_mont(x))
+  }
+  /// 通常の表現での a * b mod n
+  pub fn mul_mod(&self, a: u64, b: u64) -> u64 {
+    self.from_mont(self.mul(self.to_mont(a), self.to_mont(b)))
+  }
+  /// 通常の表現での a^e mod n
+  pub fn pow_mod(&self, a: u64, e: u64) -> u64 {
+    self.from_mont(self.pow(self.to_mont(a), e))
+  }
+}
+
+#[test]
+fn test_montgomery64() {
+  let mut rng = crate::xorshift::Xorshift::new();
+  for n in [1u64, 3, 998244353, 1_000_000_007, (1 << 61) - 1, (1 << 62) - 1, (1 << 62) - 57] {
+    let mg = Montgomery64::new(n);
+    for _ in 0..1000 {
+      let a = rng.rand(n);
+      let b = rng.rand(n);
+      assert_eq!(mg.from_mont(mg.to_mont(a)), a);
+      assert_eq!(mg.mul_mod(a, b) as u128, a as u128 * b as u128 % n as u128);
+      let (am, bm) = (mg.to_mont(a), mg.to_mont(b));
+      assert_eq!(mg.from_mont(mg.add(am, bm)) as u128, (a as u128 + b as u128) % n as u128);
+      assert_eq!(mg.from_mont(mg.sub(am, bm)) as u128, (a as u128 + n as u128 - b as u128) % n as u128);
+    }
+  }
+  let mg = Montgomery64::new(1_000_000_007);
+  assert_eq!(mg.pow_mod(2, 30), 73741817);
+  let mg = Montgomery64::new(15);
+  for a in 0..15 {
+    let inv = mg.inv(mg.to_mont(a)).map(|x| mg.from_mont(x));
+    match inv {
+      Some(x) => assert_eq!(a * x % 15, 1),
+      None => assert!(a % 3 == 0 || a % 5 == 0),
+    }
+  }
+}
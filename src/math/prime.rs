@@ -1,5 +1,7 @@
 use cargo_snippet::snippet;
 
+use super::montgomery::Montgomery64;
+
 #[snippet("prime")]
 #[snippet("factors")]
 fn factors(x: usize) -> Vec<usize> {
@@ -96,3 +98,451 @@ fn pow(x: usize, n: usize) -> usize {
   }
   ret
 }
+
+#[snippet(name = "is_prime_u64", include = "Montgomery64")]
+fn miller_rabin(n: u64, to: impl Fn(u64) -> u64, mul: impl Fn(u64, u64) -> u64) -> bool {
+  let s = (n - 1).trailing_zeros();
+  let d = (n - 1) >> s;
+  let one = to(1);
+  let minus_one = to(n - 1);
+  for a in [2, 325, 9375, 28178, 450775, 9780504, 1795265022] {
+    if a % n == 0 {
+      continue;
+    }
+    let (mut x, mut base, mut e) = (one, to(a), d);
+    while e > 0 {
+      if e & 1 == 1 {
+        x = mul(x, base);
+      }
+      base = mul(base, base);
+      e >>= 1;
+    }
+    if x == one || x == minus_one {
+      continue;
+    }
+    let mut composite = true;
+    for _ in 1..s {
+      x = mul(x, x);
+      if x == minus_one {
+        composite = false;
+        break;
+      }
+    }
+    if composite {
+      return false;
+    }
+  }
+  true
+}
+
+/// 決定的 Miller-Rabin 素数判定
+/// 計算量: O(log n)
+#[snippet(name = "is_prime_u64", include = "Montgomery64")]
+pub fn is_prime_u64(n: u64) -> bool {
+  if n < 2 {
+    return false;
+  }
+  for p in [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+    if n.is_multiple_of(p) {
+      return n == p;
+    }
+  }
+  if n < 1 << 62 {
+    let mg = Montgomery64::new(n);
+    miller_rabin(n, |a| mg.to_mont(a), |a, b| mg.mul(a, b))
+  } else {
+    miller_rabin(n, |a| a % n, |a, b| (a as u128 * b as u128 % n as u128) as u64)
+  }
+}
+
+/// Brent の改良版 Pollard's rho で n の非自明な約数を一つ返す
+/// n は奇数の合成数
+#[snippet(name = "factorize", include = "is_prime_u64")]
+fn pollard_rho(n: u64, to: impl Fn(u64) -> u64, mul: impl Fn(u64, u64) -> u64) -> u64 {
+  fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 {
+      a
+    } else {
+      gcd(b, a % b)
+    }
+  }
+  let add = |a: u64, b: u64| if a >= n - b { a - (n - b) } else { a + b };
+  let one = to(1);
+  let block = 1 << ((64 - n.leading_zeros()) / 8);
+  for c in 1..n {
+    let c = to(c);
+    let f = |x: u64| add(mul(x, x), c);
+    let (mut x, mut y, mut ys) = (one, to(2), one);
+    let (mut g, mut q, mut r) = (1, one, 1);
+    while g == 1 {
+      x = y;
+      for _ in 0..r {
+        y = f(y);
+      }
+      let mut k = 0;
+      while k < r && g == 1 {
+        ys = y;
+        for _ in 0..block.min(r - k) {
+          y = f(y);
+          q = mul(q, x.abs_diff(y));
+        }
+        g = gcd(q, n);
+        k += block;
+      }
+      r <<= 1;
+    }
+    if g == n {
+      g = 1;
+      while g == 1 {
+        ys = f(ys);
+        g = gcd(x.abs_diff(ys), n);
+      }
+    }
+    if g != n {
+      return g;
+    }
+  }
+  unreachable!()
+}
+
+#[snippet(name = "factorize", include = "is_prime_u64")]
+fn factorize_rec(n: u64, res: &mut Vec<u64>) {
+  if n == 1 {
+    return;
+  }
+  if is_prime_u64(n) {
+    res.push(n);
+    return;
+  }
+  let d = if n < 1 << 62 {
+    let mg = Montgomery64::new(n);
+    pollard_rho(n, |a| mg.to_mont(a), |a, b| mg.mul(a, b))
+  } else {
+    pollard_rho(n, |a| a % n, |a, b| (a as u128 * b as u128 % n as u128) as u64)
+  };
+  factorize_rec(d, res);
+  factorize_rec(n / d, res);
+}
+
+/// 素因数分解して (素数, 指数) を素数の昇順で返す
+/// 計算量: 期待 O(n^(1/4) log n)
+#[snippet(name = "factorize", include = "is_prime_u64")]
+pub fn factorize(n: u64) -> Vec<(u64, u32)> {
+  let mut n = n;
+  let mut ps = vec![];
+  for p in [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+    while n.is_multiple_of(p) {
+      ps.push(p);
+      n /= p;
+    }
+  }
+  factorize_rec(n, &mut ps);
+  ps.sort();
+  let mut res: Vec<(u64, u32)> = vec![];
+  for p in ps {
+    match res.last_mut() {
+      Some((q, e)) if *q == p => *e += 1,
+      _ => res.push((p, 1)),
+    }
+  }
+  res
+}
+
+/// 素因数分解から約数を昇順で列挙する
+#[snippet("divisors_from_factorization")]
+pub fn divisors_from_factorization(factors: &[(u64, u32)]) -> Vec<u64> {
+  let mut res = vec![1];
+  for &(p, e) in factors {
+    let len = res.len();
+    let mut pk = 1;
+    for _ in 0..e {
+      pk *= p;
+      for i in 0..len {
+        res.push(res[i] * pk);
+      }
+    }
+  }
+  res.sort();
+  res
+}
+
+/// 線形篩。最小素因数 (spf) を O(n) で前計算する
+#[snippet("LinearSieve")]
+pub struct LinearSieve {
+  n: usize,
+  spf: Vec<usize>,
+  primes: Vec<usize>,
+}
+
+#[snippet("LinearSieve")]
+impl LinearSieve {
+  /// O(n)
+  pub fn new(n: usize) -> Self {
+    let mut spf = vec![0; n + 1];
+    let mut primes = vec![];
+    for i in 2..=n {
+      if spf[i] == 0 {
+        spf[i] = i;
+        primes.push(i);
+      }
+      for &p in &primes {
+        if p > spf[i] || i * p > n {
+          break;
+        }
+        spf[i * p] = p;
+      }
+    }
+    LinearSieve { n, spf, primes }
+  }
+  /// n 以下の素数 (昇順)
+  pub fn primes(&self) -> &[usize] {
+    &self.primes
+  }
+  /// 最小素因数 (2 <= x <= n)
+  pub fn spf(&self, x: usize) -> usize {
+    self.spf[x]
+  }
+  pub fn is_prime(&self, x: usize) -> bool {
+    x >= 2 && self.spf[x] == x
+  }
+  /// (素数, 指数) を素数の昇順で返す
+  /// O(log x)
+  pub fn factorize(&self, x: usize) -> Vec<(usize, u32)> {
+    assert!(1 <= x && x <= self.n);
+    let mut x = x;
+    let mut res: Vec<(usize, u32)> = vec![];
+    while x > 1 {
+      let p = self.spf[x];
+      let mut e = 0;
+      while x.is_multiple_of(p) {
+        x /= p;
+        e += 1;
+      }
+      res.push((p, e));
+    }
+    res
+  }
+  /// 乗法的関数 f の f(1..=n) を O(n) で求める
+  /// f_pe(p, e) = f(p^e)。添字 0 の値は one (無意味)
+  pub fn multiplicative<T: Copy + std::ops::Mul<Output = T>>(&self, one: T, f_pe: impl Fn(usize, u32) -> T) -> Vec<T> {
+    let n = self.n;
+    let mut res = vec![one; n + 1];
+    // rest[x]: x から最小素因数の冪を除いたもの, exp[x]: その指数
+    let mut rest = vec![1; n + 1];
+    let mut exp = vec![0; n + 1];
+    for x in 2..=n {
+      let p = self.spf[x];
+      let y = x / p;
+      if self.spf[y] == p {
+        rest[x] = rest[y];
+        exp[x] = exp[y] + 1;
+      } else {
+        rest[x] = y;
+        exp[x] = 1;
+      }
+      res[x] = res[rest[x]] * f_pe(p, exp[x]);
+    }
+    res
+  }
+  /// オイラーの φ 関数
+  pub fn euler_phi(&self) -> Vec<usize> {
+    self.multiplicative(1, |p, e| (p - 1) * p.pow(e - 1))
+  }
+  /// メビウス関数
+  pub fn mobius(&self) -> Vec<i64> {
+    self.multiplicative(1, |_, e| if e == 1 { -1 } else { 0 })
+  }
+  /// 約数の個数
+  pub fn divisor_count(&self) -> Vec<usize> {
+    self.multiplicative(1, |_, e| e as usize + 1)
+  }
+  /// 約数の総和
+  pub fn divisor_sum(&self) -> Vec<u64> {
+    self.multiplicative(1, |p, e| (p.pow(e + 1) as u64 - 1) / (p as u64 - 1))
+  }
+}
+
+/// floor(sqrt(n))
+#[snippet("isqrt")]
+pub fn isqrt(n: u64) -> u64 {
+  let mut x = (n as f64).sqrt() as u64;
+  while x.checked_mul(x).is_none_or(|s| s > n) {
+    x -= 1;
+  }
+  while (x + 1).checked_mul(x + 1).is_some_and(|s| s <= n) {
+    x += 1;
+  }
+  x
+}
+
+/// 区間 [l, r) の素数を列挙する
+/// 計算量: O(sqrt(r) + (r - l) log log r), メモリ: O(sqrt(r) + (r - l))
+#[snippet(name = "segmented_sieve", include = "LinearSieve, isqrt")]
+pub fn segmented_sieve(l: u64, r: u64) -> Vec<u64> {
+  if l >= r {
+    return vec![];
+  }
+  let sieve = LinearSieve::new(isqrt(r - 1) as usize);
+  let mut is_prime = vec![true; (r - l) as usize];
+  for &p in sieve.primes() {
+    let p = p as u64;
+    let start = (p * p).max(l.div_ceil(p) * p);
+    for m in (start..r).step_by(p as usize) {
+      is_prime[(m - l) as usize] = false;
+    }
+  }
+  (l..r).filter(|&x| x >= 2 && is_prime[(x - l) as usize]).collect()
+}
+
+/// 区間 [l, r) の各整数を素因数分解する (l >= 1)
+/// 計算量: O(sqrt(r) + (r - l) log r), メモリ: O(sqrt(r) + (r - l) log r)
+#[snippet(name = "segmented_factorize", include = "LinearSieve, isqrt")]
+pub fn segmented_factorize(l: u64, r: u64) -> Vec<Vec<(u64, u32)>> {
+  assert!(l >= 1);
+  if l >= r {
+    return vec![];
+  }
+  let sieve = LinearSieve::new(isqrt(r - 1) as usize);
+  let mut rest = (l..r).collect::<Vec<_>>();
+  let mut res = vec![vec![]; (r - l) as usize];
+  for &p in sieve.primes() {
+    let p = p as u64;
+    for m in (l.div_ceil(p) * p..r).step_by(p as usize) {
+      let i = (m - l) as usize;
+      let mut e = 0;
+      while rest[i] % p == 0 {
+        rest[i] /= p;
+        e += 1;
+      }
+      res[i].push((p, e));
+    }
+  }
+  for (fs, &x) in res.iter_mut().zip(rest.iter()) {
+    if x > 1 {
+      fs.push((x, 1));
+    }
+  }
+  res
+}
+
+#[test]
+fn test_is_prime_u64() {
+  for n in 0..10000 {
+    assert_eq!(is_prime_u64(n as u64), is_prime(n), "{}", n);
+  }
+  for n in [561, 1105, 1729, 2465, 2821, 6601, 3215031751, 3825123056546413051] {
+    assert!(!is_prime_u64(n));
+  }
+  for n in [998244353, 1_000_000_007, 1_000_000_000_000_000_003, (1 << 61) - 1, (1 << 62) - 57, u64::MAX - 58] {
+    assert!(is_prime_u64(n));
+  }
+  assert!(!is_prime_u64(u64::MAX));
+  assert!(!is_prime_u64(4611686014132420609)); // (2^31 - 1)^2
+}
+
+#[test]
+fn test_factorize() {
+  assert_eq!(factorize(1), vec![]);
+  assert_eq!(factorize(1_000_000_000_000_000_000), vec![(2, 18), (5, 18)]);
+  assert_eq!(factorize(999381247093216751), vec![(999665081, 1), (999716071, 1)]);
+  assert_eq!(factorize(4611686014132420609), vec![(2147483647, 2)]);
+  assert_eq!(factorize(u64::MAX), vec![(3, 1), (5, 1), (17, 1), (257, 1), (641, 1), (65537, 1), (6700417, 1)]);
+  let mut rng = crate::xorshift::Xorshift::new();
+  for _ in 0..100 {
+    let n = rng.rand(1 << 62) + 1;
+    let fs = factorize(n);
+    assert_eq!(fs.iter().map(|&(p, e)| p.pow(e)).product::<u64>(), n);
+    assert!(fs.iter().all(|&(p, _)| is_prime_u64(p)));
+  }
+  for n in 1..2000 {
+    let fs = factorize(n as u64);
+    let ps = factors(n);
+    assert_eq!(fs.iter().map(|&(_, e)| e as usize).sum::<usize>(), ps.len());
+    assert!(fs.iter().all(|&(p, _)| ps.contains(&(p as usize))));
+  }
+}
+
+#[test]
+fn test_divisors_from_factorization() {
+  for n in 1..2000 {
+    let ds = divisors_from_factorization(&factorize(n as u64));
+    assert_eq!(ds, divisor(n).into_iter().map(|d| d as u64).collect::<Vec<_>>());
+  }
+}
+
+#[test]
+fn test_linear_sieve() {
+  let n = 3000;
+  let ls = LinearSieve::new(n);
+  assert_eq!(ls.primes().len(), sieve(n));
+  assert_eq!(&ls.primes()[..5], &[2, 3, 5, 7, 11]);
+  let phi = ls.euler_phi();
+  let mu = ls.mobius();
+  let d = ls.divisor_count();
+  let sigma = ls.divisor_sum();
+  for x in 1..=n {
+    assert_eq!(ls.is_prime(x), is_prime(x));
+    if x >= 2 {
+      assert_eq!(ls.spf(x), firstfac(x));
+    }
+    let fs = ls.factorize(x);
+    assert_eq!(fs.iter().map(|&(p, e)| p.pow(e)).product::<usize>(), x);
+    let ds = divisor(x);
+    assert_eq!(d[x], ds.len());
+    assert_eq!(sigma[x], ds.iter().sum::<usize>() as u64);
+    assert_eq!(phi[x], (1..=x).filter(|&k| gcd(k, x) == 1).count());
+    let expected_mu = if fs.iter().any(|&(_, e)| e >= 2) { 0 } else if fs.len().is_multiple_of(2) { 1 } else { -1 };
+    assert_eq!(mu[x], expected_mu);
+  }
+  fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+      a
+    } else {
+      gcd(b, a % b)
+    }
+  }
+}
+
+#[test]
+fn test_isqrt() {
+  for n in 0..10000u64 {
+    let x = isqrt(n);
+    assert!(x * x <= n && n < (x + 1) * (x + 1));
+  }
+  assert_eq!(isqrt(u64::MAX), (1 << 32) - 1);
+  assert_eq!(isqrt(u64::MAX - 1), (1 << 32) - 1);
+  assert_eq!(isqrt(((1 << 32) - 1) * ((1 << 32) - 1)), (1 << 32) - 1);
+  assert_eq!(isqrt(((1 << 32) - 1) * ((1 << 32) - 1) - 1), (1 << 32) - 2);
+  assert_eq!(isqrt(999_999_999_999_999_999), 999_999_999);
+}
+
+#[test]
+fn test_segmented_sieve() {
+  assert_eq!(segmented_sieve(0, 30), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+  assert_eq!(segmented_sieve(10, 10), vec![]);
+  for l in 0..200 {
+    for r in l..200 {
+      let expected = (l..r).filter(|&x| is_prime(x as usize)).collect::<Vec<_>>();
+      assert_eq!(segmented_sieve(l, r), expected);
+    }
+  }
+  let (l, r) = (1_000_000_000_000, 1_000_000_001_000);
+  let ps = segmented_sieve(l, r);
+  assert_eq!(ps, (l..r).filter(|&x| is_prime_u64(x)).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_segmented_factorize() {
+  for l in 1..100 {
+    for r in l..100 {
+      let fs = segmented_factorize(l, r);
+      for (x, f) in (l..r).zip(fs) {
+        assert_eq!(f, factorize(x));
+      }
+    }
+  }
+  let (l, r) = (1_000_000_000_000, 1_000_000_001_000);
+  for (x, f) in (l..r).zip(segmented_factorize(l, r)) {
+    assert_eq!(f, factorize(x));
+  }
+}
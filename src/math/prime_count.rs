@@ -0,0 +1,148 @@
+use cargo_snippet::snippet;
+use std::ops::{Mul, Sub};
+
+use super::modint::ModInt;
+use super::prime::{isqrt, LinearSieve};
+use crate::polynomial::interpolation::interpolate_consecutive;
+
+/// v = floor(n / i) の形の全ての v について Σ_{p <= v, p: 素数} f(p) を持つ表
+#[snippet(name = "prime_count", include = "LinearSieve, isqrt, interpolate_consecutive")]
+#[derive(Debug, Clone)]
+pub struct PrimeSumTable<T> {
+  n: u64,
+  sq: u64,
+  /// small[v] = g(v) (v <= sq)
+  small: Vec<T>,
+  /// large[i] = g(n / i) (i <= sq)
+  large: Vec<T>,
+}
+
+#[snippet(name = "prime_count", include = "LinearSieve, isqrt, interpolate_consecutive")]
+impl<T: Copy> PrimeSumTable<T> {
+  pub fn n(&self) -> u64 {
+    self.n
+  }
+  /// v は floor(n / i) の形であること
+  pub fn get(&self, v: u64) -> T {
+    if v <= self.sq {
+      self.small[v as usize]
+    } else {
+      self.large[(self.n / v) as usize]
+    }
+  }
+}
+
+/// Lucy DP
+/// init(v) = Σ_{2 <= i <= v} f(i), f は完全乗法的
+/// 計算量: O(n^(3/4) / log n)
+#[snippet(name = "prime_count", include = "LinearSieve, isqrt, interpolate_consecutive")]
+fn lucy<T>(n: u64, init: impl Fn(u64) -> T, f: impl Fn(u64) -> T) -> PrimeSumTable<T>
+where
+  T: Copy + Sub<Output = T> + Mul<Output = T>,
+{
+  let sq = isqrt(n);
+  let mut small = (0..=sq).map(&init).collect::<Vec<_>>();
+  let mut large = (0..=sq).map(|i| init(n.checked_div(i).unwrap_or(0))).collect::<Vec<_>>();
+  for &p in LinearSieve::new(sq as usize).primes() {
+    let p = p as u64;
+    let gp = small[p as usize - 1];
+    let fp = f(p);
+    let p2 = p * p;
+    for i in (1..=sq).take_while(|&i| n / i >= p2) {
+      let ip = i * p;
+      let g = if ip <= sq { large[ip as usize] } else { small[(n / ip) as usize] };
+      large[i as usize] = large[i as usize] - fp * (g - gp);
+    }
+    for v in (p2..=sq).rev() {
+      small[v as usize] = small[v as usize] - fp * (small[(v / p) as usize] - gp);
+    }
+  }
+  PrimeSumTable { n, sq, small, large }
+}
+
+/// n 以下の素数の個数
+/// 計算量: O(n^(3/4) / log n)
+#[snippet(name = "prime_count", include = "LinearSieve, isqrt, interpolate_consecutive")]
+pub fn prime_count(n: u64) -> u64 {
+  lucy(n, |v| v.saturating_sub(1), |_| 1).get(n)
+}
+
+/// Σ_{p <= v} p^k を v = floor(n / i) 全てについて求める
+/// 法は k + 1 より大きい素数
+#[snippet(name = "prime_count", include = "LinearSieve, isqrt, interpolate_consecutive")]
+pub fn prime_pow_sum<T: ModInt>(n: u64, k: u32) -> PrimeSumTable<T> {
+  let d = k as usize + 1;
+  // ys[j] = Σ_{1 <= i <= j} i^k
+  let mut ys = vec![T::default(); d + 1];
+  for j in 1..=d {
+    ys[j] = ys[j - 1] + T::from(j as i64).pow(k as u64);
+  }
+  let m = T::modulus() as u64;
+  lucy(
+    n,
+    |v| interpolate_consecutive(&ys, T::from((v % m) as i64)) - T::from(1),
+    |p| T::from((p % m) as i64).pow(k as u64),
+  )
+}
+
+/// f(p) = Σ coef[k] p^k に対して Σ_{p <= v} f(p) を v = floor(n / i) 全てについて求める
+#[snippet(name = "prime_count", include = "LinearSieve, isqrt, interpolate_consecutive")]
+pub fn prime_sum<T: ModInt>(n: u64, coef: &[T]) -> PrimeSumTable<T> {
+  let sq = isqrt(n);
+  let mut small = vec![T::default(); sq as usize + 1];
+  let mut large = vec![T::default(); sq as usize + 1];
+  for (k, &c) in coef.iter().enumerate() {
+    let table = prime_pow_sum::<T>(n, k as u32);
+    for (s, &t) in small.iter_mut().zip(table.small.iter()) {
+      *s += c * t;
+    }
+    for (l, &t) in large.iter_mut().zip(table.large.iter()) {
+      *l += c * t;
+    }
+  }
+  PrimeSumTable { n, sq, small, large }
+}
+
+#[test]
+fn test_prime_count() {
+  let mut cnt = 0;
+  for n in 0..3000 {
+    if crate::math::prime::is_prime_u64(n) {
+      cnt += 1;
+    }
+    assert_eq!(prime_count(n), cnt);
+  }
+  assert_eq!(prime_count(1_000_000), 78498);
+  assert_eq!(prime_count(1_000_000_000), 50847534);
+}
+
+#[test]
+fn test_prime_sum() {
+  use crate::math::modint::Mod;
+  let n = 10000;
+  let primes = LinearSieve::new(n as usize).primes().iter().map(|&p| p as u64).collect::<Vec<_>>();
+  let keys = (1..=n).map(|i| n / i).collect::<Vec<_>>();
+  for k in 0..4 {
+    let table = prime_pow_sum::<Mod>(n, k);
+    for &v in &keys {
+      let expected = primes.iter().filter(|&&p| p <= v).map(|&p| Mod::new(p as i64).pow(k as u64)).sum::<Mod>();
+      assert_eq!(table.get(v), expected);
+    }
+  }
+  let coef = [Mod::new(3), Mod::new(-1), Mod::new(0), Mod::new(2)];
+  let table = prime_sum(n, &coef);
+  for &v in &keys {
+    let expected = primes
+      .iter()
+      .filter(|&&p| p <= v)
+      .map(|&p| {
+        let p = Mod::new(p as i64);
+        coef[0] + coef[1] * p + coef[3] * p.pow(3)
+      })
+      .sum::<Mod>();
+    assert_eq!(table.get(v), expected);
+  }
+  // 法より大きい値でも多項式として正しく扱える
+  let table = prime_pow_sum::<Mod>(1_000_000_000, 1);
+  assert_eq!(table.get(1_000_000_000), Mod::new(24739512092254535 % 998244353));
+}
@@ -1,60 +1,151 @@
+use std::ops::{Div, Mul, Neg, Rem};
+
+use super::bigint::BigInt;
+
+/// Ratio の分子・分母に使える整数型
+pub trait RationalInt:
+  Clone + Ord + std::ops::Add<Output = Self> + std::ops::Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Rem<Output = Self> + Neg<Output = Self> + From<i64>
+{
+  fn to_f64(&self) -> f64;
+  /// 溢れたら None
+  fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+  /// 溢れたら None
+  fn checked_add(&self, rhs: &Self) -> Option<Self>;
+}
+impl RationalInt for i64 {
+  fn to_f64(&self) -> f64 {
+    *self as f64
+  }
+  fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+    i64::checked_mul(*self, *rhs)
+  }
+  fn checked_add(&self, rhs: &Self) -> Option<Self> {
+    i64::checked_add(*self, *rhs)
+  }
+}
+impl RationalInt for i128 {
+  fn to_f64(&self) -> f64 {
+    *self as f64
+  }
+  fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+    i128::checked_mul(*self, *rhs)
+  }
+  fn checked_add(&self, rhs: &Self) -> Option<Self> {
+    i128::checked_add(*self, *rhs)
+  }
+}
+impl RationalInt for BigInt {
+  fn to_f64(&self) -> f64 {
+    BigInt::to_f64(self)
+  }
+  fn checked_mul(&self, rhs: &Self) -> Option<Self> {
+    Some(self * rhs)
+  }
+  fn checked_add(&self, rhs: &Self) -> Option<Self> {
+    Some(self + rhs)
+  }
+}
+
+/// 既約分数。分母は常に正
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
-struct Rational {
-  numerator: i64,
-  denominator: i64,
+pub struct Ratio<T> {
+  numerator: T,
+  denominator: T,
 }
 
-impl Rational {
-  pub fn new(numerator: i64, denominator: i64) -> Self {
-    assert_ne!(denominator, 0);
-    let sign = if denominator.is_negative() { -1 } else { 1 };
-    let g = Self::gcd(numerator.abs(), denominator.abs());
-    Self {
-      numerator: sign * numerator / g,
-      denominator: sign * denominator / g,
+pub type Rational = Ratio<i64>;
+
+impl<T: RationalInt> Ratio<T> {
+  pub fn new(numerator: T, denominator: T) -> Self {
+    let zero = T::from(0);
+    assert!(denominator != zero);
+    let g = Self::gcd(Self::abs(numerator.clone()), Self::abs(denominator.clone()));
+    let (numerator, denominator) = (numerator / g.clone(), denominator / g);
+    if denominator < zero {
+      Self {
+        numerator: -numerator,
+        denominator: -denominator,
+      }
+    } else {
+      Self { numerator, denominator }
     }
   }
-  fn gcd(a: i64, b: i64) -> i64 {
-    if b == 0 {
+  pub fn numerator(&self) -> &T {
+    &self.numerator
+  }
+  pub fn denominator(&self) -> &T {
+    &self.denominator
+  }
+  /// 1 / self
+  pub fn recip(&self) -> Self {
+    assert!(self.numerator != T::from(0), "reciprocal of zero");
+    Self::new(self.denominator.clone(), self.numerator.clone())
+  }
+  /// self 以下の最大の整数
+  pub fn floor(&self) -> T {
+    let q = self.numerator.clone() / self.denominator.clone();
+    if self.numerator < T::from(0) && q.clone() * self.denominator.clone() != self.numerator {
+      q - T::from(1)
+    } else {
+      q
+    }
+  }
+  /// self 以上の最小の整数
+  pub fn ceil(&self) -> T {
+    -(-self.clone()).floor()
+  }
+  pub fn to_f64(&self) -> f64 {
+    self.numerator.to_f64() / self.denominator.to_f64()
+  }
+  fn abs(x: T) -> T {
+    if x < T::from(0) {
+      -x
+    } else {
+      x
+    }
+  }
+  fn gcd(a: T, b: T) -> T {
+    if b == T::from(0) {
       a
     } else {
-      Self::gcd(b, a % b)
+      let r = a % b.clone();
+      Self::gcd(b, r)
     }
   }
 }
 
-impl std::ops::Add for Rational {
+impl<T: RationalInt> std::ops::Add for Ratio<T> {
   type Output = Self;
   fn add(self, rhs: Self) -> Self::Output {
-    let numerator = self.numerator * rhs.denominator + rhs.numerator * self.denominator;
+    let numerator = self.numerator * rhs.denominator.clone() + rhs.numerator * self.denominator.clone();
     let denominator = self.denominator * rhs.denominator;
     Self::new(numerator, denominator)
   }
 }
 
 use std::ops::Add;
-impl std::ops::AddAssign for Rational {
+impl<T: RationalInt> std::ops::AddAssign for Ratio<T> {
   fn add_assign(&mut self, rhs: Self) {
-    *self = self.add(rhs);
+    *self = self.clone().add(rhs);
   }
 }
-impl std::ops::Sub for Rational {
+impl<T: RationalInt> std::ops::Sub for Ratio<T> {
   type Output = Self;
   fn sub(self, rhs: Self) -> Self::Output {
-    let numerator = self.numerator * rhs.denominator - rhs.numerator * self.denominator;
+    let numerator = self.numerator * rhs.denominator.clone() - rhs.numerator * self.denominator.clone();
     let denominator = self.denominator * rhs.denominator;
     Self::new(numerator, denominator)
   }
 }
 
 use std::ops::Sub;
-impl std::ops::SubAssign for Rational {
+impl<T: RationalInt> std::ops::SubAssign for Ratio<T> {
   fn sub_assign(&mut self, rhs: Self) {
-    *self = self.sub(rhs);
+    *self = self.clone().sub(rhs);
   }
 }
 
-impl std::ops::Mul for Rational {
+impl<T: RationalInt> std::ops::Mul for Ratio<T> {
   type Output = Self;
   fn mul(self, rhs: Self) -> Self::Output {
     let numerator = self.numerator * rhs.numerator;
@@ -63,19 +154,144 @@ impl std::ops::Mul for Rational {
   }
 }
 
-impl Ord for Rational {
+impl<T: RationalInt> Ord for Ratio<T> {
   fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-    let l = self.numerator * other.denominator;
-    let r = other.numerator * self.denominator;
+    let l = self.numerator.clone() * other.denominator.clone();
+    let r = other.numerator.clone() * self.denominator.clone();
     l.cmp(&r)
   }
 }
-impl PartialOrd for Rational {
+impl<T: RationalInt> PartialOrd for Ratio<T> {
   fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
     Some(self.cmp(other))
   }
 }
 
+impl<T: RationalInt> std::ops::MulAssign for Ratio<T> {
+  fn mul_assign(&mut self, rhs: Self) {
+    *self = self.clone() * rhs;
+  }
+}
+impl<T: RationalInt> std::ops::Div for Ratio<T> {
+  type Output = Self;
+  fn div(self, rhs: Self) -> Self::Output {
+    assert!(rhs.numerator != T::from(0), "division by zero");
+    let numerator = self.numerator * rhs.denominator;
+    let denominator = self.denominator * rhs.numerator;
+    Self::new(numerator, denominator)
+  }
+}
+impl<T: RationalInt> std::ops::DivAssign for Ratio<T> {
+  fn div_assign(&mut self, rhs: Self) {
+    *self = self.clone() / rhs;
+  }
+}
+impl<T: RationalInt> std::ops::Neg for Ratio<T> {
+  type Output = Self;
+  fn neg(self) -> Self::Output {
+    Self {
+      numerator: -self.numerator,
+      denominator: self.denominator,
+    }
+  }
+}
+impl<T: RationalInt> From<T> for Ratio<T> {
+  fn from(v: T) -> Self {
+    Self {
+      numerator: v,
+      denominator: T::from(1),
+    }
+  }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseRatioError;
+
+impl<T: RationalInt + std::str::FromStr> std::str::FromStr for Ratio<T> {
+  type Err = ParseRatioError;
+  /// "p/q", "p", "-1.25" の形式
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let int = |s: &str| s.parse::<T>().map_err(|_| ParseRatioError);
+    if let Some((p, q)) = s.split_once('/') {
+      let q = int(q)?;
+      if q == T::from(0) {
+        return Err(ParseRatioError);
+      }
+      return Ok(Self::new(int(p)?, q));
+    }
+    if let Some((i, f)) = s.split_once('.') {
+      if f.is_empty() || !f.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(ParseRatioError);
+      }
+      // 桁が多すぎて T に収まらなければエラー
+      let den = (0..f.len()).try_fold(T::from(1), |acc, _| acc.checked_mul(&T::from(10)));
+      let den = den.ok_or(ParseRatioError)?;
+      let sign = if i.starts_with('-') { T::from(-1) } else { T::from(1) };
+      let i = if i.is_empty() || i == "-" || i == "+" { T::from(0) } else { int(i)? };
+      let f = sign * int(f)?;
+      let num = i.checked_mul(&den).and_then(|x| x.checked_add(&f));
+      return Ok(Self::new(num.ok_or(ParseRatioError)?, den));
+    }
+    Ok(Self::from(int(s)?))
+  }
+}
+
+/// Stern-Brocot 木上の二分探索
+/// pred は 0/1 で true で、分数の増加に対して true から false に一度だけ変わる
+/// 0 <= p <= max_num, 1 <= q <= max_den の p/q のうち
+/// pred が true になる最大のものと false になる最小のもの (なければ (1, 0)) を返す
+/// 計算量: pred の呼び出し O(log^2 max(max_num, max_den)) 回
+pub fn stern_brocot_search(max_num: i64, max_den: i64, pred: impl Fn(i64, i64) -> bool) -> ((i64, i64), (i64, i64)) {
+  let (mut lo, mut hi) = ((0i64, 1i64), (1i64, 0i64));
+  // (a + k b) が範囲内で cond を満たす最大の k
+  let max_step = |a: (i64, i64), b: (i64, i64), cond: &dyn Fn(i64, i64) -> bool| {
+    let ok = |k: i64| {
+      let (p, q) = (a.0 + k * b.0, a.1 + k * b.1);
+      p <= max_num && q <= max_den && cond(p, q)
+    };
+    if !ok(1) {
+      return 0;
+    }
+    let mut k = 1;
+    while ok(2 * k) {
+      k *= 2;
+    }
+    let (mut l, mut r) = (k, 2 * k);
+    while r - l > 1 {
+      let m = (l + r) / 2;
+      if ok(m) {
+        l = m;
+      } else {
+        r = m;
+      }
+    }
+    l
+  };
+  loop {
+    let k = max_step(lo, hi, &|p, q| pred(p, q));
+    lo = (lo.0 + k * hi.0, lo.1 + k * hi.1);
+    let l = max_step(hi, lo, &|p, q| !pred(p, q));
+    hi = (hi.0 + l * lo.0, hi.1 + l * lo.1);
+    if k == 0 && l == 0 {
+      break;
+    }
+  }
+  (lo, hi)
+}
+
+/// 分母が max_den 以下の分数のうち x に最も近いもの (同じ距離なら小さい方)
+pub fn best_approximation(x: &Rational, max_den: i64) -> Rational {
+  assert!(max_den >= 1);
+  let k = x.floor();
+  let r = *x - Rational::from(k);
+  let (rn, rd) = (r.numerator as i128, r.denominator as i128);
+  let (lo, hi) = stern_brocot_search(max_den, max_den, |p, q| p as i128 * rd <= rn * q as i128);
+  // r - lo <= hi - r を i128 で比べる
+  let (a, b, c, d) = (lo.0 as i128, lo.1 as i128, hi.0 as i128, hi.1 as i128);
+  let best = if (rn * b - a * rd) * d <= (c * rd - rn * d) * b { lo } else { hi };
+  Rational::new(best.0, best.1) + Rational::from(k)
+}
+
 #[test]
 fn ord_test() {
   let a = Rational::new(1, 2);
@@ -91,3 +307,91 @@ fn ord_test() {
   let b = Rational::new(0, 4);
   assert_eq!(a.cmp(&b), std::cmp::Ordering::Equal);
 }
+
+#[test]
+fn bigint_ratio_test() {
+  // 調和数 H_50 は i64 では溢れる
+  let mut h = Ratio::new(BigInt::from(0), BigInt::from(1));
+  for i in 1..=50 {
+    h += Ratio::new(BigInt::from(1), BigInt::from(i));
+  }
+  assert_eq!(h.numerator().to_string(), "13943237577224054960759");
+  assert_eq!(h.denominator().to_string(), "3099044504245996706400");
+  let a = Ratio::new(BigInt::from(-6), BigInt::from(-4));
+  assert_eq!(a, Ratio::new(BigInt::from(3), BigInt::from(2)));
+  assert!(a > Ratio::new(BigInt::from(-7), BigInt::from(2)));
+  assert_eq!(Ratio::<i128>::new(3, -9) * Ratio::new(-3, 1), Ratio::new(1, 1));
+}
+
+#[test]
+fn arithmetic_test() {
+  let a = Rational::new(3, 4);
+  let b = Rational::new(-2, 5);
+  assert_eq!(a / b, Rational::new(-15, 8));
+  assert_eq!(-a, Rational::new(-3, 4));
+  assert_eq!(b.recip(), Rational::new(-5, 2));
+  let mut c = a;
+  c *= b;
+  c /= b;
+  assert_eq!(c, a);
+  for (n, d, f, cl) in [(7, 2, 3, 4), (-7, 2, -4, -3), (6, 3, 2, 2), (-6, 3, -2, -2), (0, 5, 0, 0), (-1, 3, -1, 0)] {
+    let x = Rational::new(n, d);
+    assert_eq!(x.floor(), f);
+    assert_eq!(x.ceil(), cl);
+  }
+  assert_eq!(Rational::new(1, 8).to_f64(), 0.125);
+  assert_eq!("3/-6".parse::<Rational>(), Ok(Rational::new(-1, 2)));
+  assert_eq!("42".parse::<Rational>(), Ok(Rational::new(42, 1)));
+  assert_eq!("-1.25".parse::<Rational>(), Ok(Rational::new(-5, 4)));
+  assert_eq!("-0.5".parse::<Rational>(), Ok(Rational::new(-1, 2)));
+  assert_eq!(".5".parse::<Rational>(), Ok(Rational::new(1, 2)));
+  assert!("1/0".parse::<Rational>().is_err());
+  assert!("1.".parse::<Rational>().is_err());
+  assert!("a/2".parse::<Rational>().is_err());
+  // 溢れる入力はパニックせずエラーにする
+  assert!("0.0000000000000000001".parse::<Rational>().is_err());
+  assert!("9223372036854775807.5".parse::<Rational>().is_err());
+  assert_eq!("0.000000000000000001".parse::<Rational>(), Ok(Rational::new(1, 1_000_000_000_000_000_000)));
+  assert_eq!(
+    "0.0000000000000000001".parse::<Ratio<BigInt>>(),
+    Ok(Ratio::new(BigInt::from(1), BigInt::from(10).pow(19)))
+  );
+}
+
+#[test]
+fn stern_brocot_test() {
+  // sqrt(2) の近似: p^2 <= 2 q^2
+  let (lo, hi) = stern_brocot_search(100, 100, |p, q| p * p <= 2 * q * q);
+  assert_eq!(lo, (41, 29));
+  assert_eq!(hi, (99, 70));
+  // 常に true
+  assert_eq!(stern_brocot_search(10, 10, |_, _| true), ((10, 1), (1, 0)));
+  // 区間 (1/3, 2/5) にある分母最小の分数の下側
+  let (lo, hi) = stern_brocot_search(3, 3, |p, q| 3 * p <= q);
+  assert_eq!((lo, hi), ((1, 3), (1, 2)));
+  // 全探索と比較
+  for n in 1..12 {
+    for (a, b) in [(1, 3), (2, 7), (5, 4), (7, 3), (0, 1)] {
+      let (lo, hi) = stern_brocot_search(n, n, |p, q| p * b <= a * q);
+      let mut fracs = vec![];
+      for p in 0..=n {
+        for q in 1..=n {
+          fracs.push(Rational::new(p, q));
+        }
+      }
+      let x = Rational::new(a, b);
+      let elo = fracs.iter().filter(|&&f| f <= x).max().unwrap();
+      assert_eq!(Rational::new(lo.0, lo.1), *elo);
+      match fracs.iter().filter(|&&f| f > x).min() {
+        Some(ehi) => assert_eq!(Rational::new(hi.0, hi.1), *ehi),
+        None => assert_eq!(hi, (1, 0)),
+      }
+    }
+  }
+  let pi = Rational::new(314159265358979, 100000000000000);
+  assert_eq!(best_approximation(&pi, 10), Rational::new(22, 7));
+  assert_eq!(best_approximation(&pi, 200), Rational::new(355, 113));
+  assert_eq!(best_approximation(&-pi, 10), Rational::new(-22, 7));
+  assert_eq!(best_approximation(&Rational::new(1, 4), 2), Rational::new(0, 1));
+  assert_eq!(best_approximation(&Rational::new(5, 2), 1), Rational::new(2, 1));
+}
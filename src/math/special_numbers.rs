@@ -0,0 +1,210 @@
+use cargo_snippet::snippet;
+
+use super::combination::Binomial;
+use super::modint::{ModInt, StaticModInt};
+use crate::polynomial::fps::FormalPowerSeries;
+use crate::polynomial::ntt::convolution;
+use crate::polynomial::shift::taylor_shift;
+
+/// 符号なし第一種スターリング数 c(i, k) (0 <= k <= i <= n) の表
+/// 計算量: O(n^2)
+#[snippet(name = "special_numbers_table", include = "Binomial")]
+pub fn stirling1_table<T: ModInt>(n: usize) -> Vec<Vec<T>> {
+  let mut c = vec![vec![T::default(); n + 1]; n + 1];
+  c[0][0] = T::from(1);
+  for i in 0..n {
+    for k in 0..=i {
+      let v = c[i][k];
+      c[i + 1][k + 1] += v;
+      c[i + 1][k] += v * T::from(i as i64);
+    }
+  }
+  c
+}
+
+/// 第二種スターリング数 S(i, k) (0 <= k <= i <= n) の表
+/// 計算量: O(n^2)
+#[snippet(name = "special_numbers_table", include = "Binomial")]
+pub fn stirling2_table<T: ModInt>(n: usize) -> Vec<Vec<T>> {
+  let mut s = vec![vec![T::default(); n + 1]; n + 1];
+  s[0][0] = T::from(1);
+  for i in 0..n {
+    for k in 0..=i {
+      let v = s[i][k];
+      s[i + 1][k + 1] += v;
+      s[i + 1][k] += v * T::from(k as i64);
+    }
+  }
+  s
+}
+
+/// ベル数 B_0, ..., B_n (ベルの三角形)
+/// 計算量: O(n^2)
+#[snippet(name = "special_numbers_table", include = "Binomial")]
+pub fn bell_table<T: ModInt>(n: usize) -> Vec<T> {
+  let mut res = vec![T::from(1)];
+  let mut row = vec![T::from(1)];
+  for _ in 0..n {
+    let mut next = vec![*row.last().unwrap()];
+    for &x in &row {
+      let v = *next.last().unwrap() + x;
+      next.push(v);
+    }
+    res.push(next[0]);
+    row = next;
+  }
+  res
+}
+
+/// ベルヌーイ数 B_0, ..., B_n (B_1 = -1/2)
+/// 法は n + 1 より大きい素数
+/// 計算量: O(n^2)
+#[snippet(name = "special_numbers_table", include = "Binomial")]
+pub fn bernoulli_table<T: ModInt>(n: usize) -> Vec<T> {
+  let mut binom = Binomial::<T>::new(n + 1);
+  let mut b = vec![T::from(1)];
+  for m in 1..=n {
+    let s = (0..m).map(|k| binom.comb(m + 1, k) * b[k]).sum::<T>();
+    b.push(-s / T::from(m as i64 + 1));
+  }
+  b
+}
+
+/// 分割数 p(0), ..., p(n) (五角数定理)
+/// 計算量: O(n sqrt(n))
+#[snippet(name = "special_numbers_table", include = "Binomial")]
+pub fn partition_table<T: ModInt>(n: usize) -> Vec<T> {
+  let mut p = vec![T::default(); n + 1];
+  p[0] = T::from(1);
+  for i in 1..=n {
+    for k in 1.. {
+      let g1 = k * (3 * k - 1) / 2;
+      if g1 > i {
+        break;
+      }
+      let mut s = p[i - g1];
+      let g2 = k * (3 * k + 1) / 2;
+      if g2 <= i {
+        s += p[i - g2];
+      }
+      if k % 2 == 1 {
+        p[i] += s;
+      } else {
+        p[i] -= s;
+      }
+    }
+  }
+  p
+}
+
+/// 符号なし第一種スターリング数 c(n, 0), ..., c(n, n)
+/// x(x+1)...(x+n-1) を f_2m(x) = f_m(x) f_m(x + m) で倍々に計算する
+/// 計算量: O(n log n)
+#[snippet(name = "special_numbers", include = "Binomial, FormalPowerSeries, taylor_shift")]
+pub fn stirling1_row<const M: u32>(n: usize) -> Vec<StaticModInt<M>> {
+  let mut f = vec![StaticModInt::new(1)];
+  for bit in (0..usize::BITS - n.leading_zeros()).rev() {
+    let m = f.len() - 1;
+    let g = taylor_shift(&f, StaticModInt::new(m as i64));
+    f = convolution(&f, &g);
+    if n >> bit & 1 == 1 {
+      // f <- f * (x + 2m)
+      let c = StaticModInt::new(2 * m as i64);
+      let mut h = vec![StaticModInt::new(0); f.len() + 1];
+      for (i, &x) in f.iter().enumerate() {
+        h[i] += x * c;
+        h[i + 1] += x;
+      }
+      f = h;
+    }
+  }
+  f
+}
+
+/// 第二種スターリング数 S(n, 0), ..., S(n, n)
+/// S(n, k) = Σ_i (-1)^(k-i) i^n / (i! (k-i)!)
+/// 計算量: O(n log n)
+#[snippet(name = "special_numbers", include = "Binomial, FormalPowerSeries, taylor_shift")]
+pub fn stirling2_row<const M: u32>(n: usize) -> Vec<StaticModInt<M>> {
+  let mut binom = Binomial::<StaticModInt<M>>::new(n);
+  let a = (0..=n).map(|i| StaticModInt::new(i as i64).pow(n as u64) * binom.inv_fact(i)).collect::<Vec<_>>();
+  let b = (0..=n)
+    .map(|j| if j % 2 == 0 { binom.inv_fact(j) } else { -binom.inv_fact(j) })
+    .collect::<Vec<_>>();
+  let mut s = convolution(&a, &b);
+  s.truncate(n + 1);
+  s
+}
+
+/// ベル数 B_n = Σ_k S(n, k)
+/// 計算量: O(n log n)
+#[snippet(name = "special_numbers", include = "Binomial, FormalPowerSeries, taylor_shift")]
+pub fn bell<const M: u32>(n: usize) -> StaticModInt<M> {
+  stirling2_row(n).into_iter().sum()
+}
+
+/// ベルヌーイ数 B_0, ..., B_n (B_1 = -1/2)
+/// 指数型母関数 x / (e^x - 1) から求める
+/// 計算量: O(n log n)
+#[snippet(name = "special_numbers", include = "Binomial, FormalPowerSeries, taylor_shift")]
+pub fn bernoulli<const M: u32>(n: usize) -> Vec<StaticModInt<M>> {
+  let mut binom = Binomial::<StaticModInt<M>>::new(n + 1);
+  let f = (0..=n).map(|i| binom.inv_fact(i + 1)).collect::<Vec<_>>();
+  let g = FormalPowerSeries::new(f).inv(n + 1);
+  g.a.into_iter().enumerate().map(|(i, x)| x * binom.fact(i)).collect()
+}
+
+/// 分割数 p(0), ..., p(n)
+/// 1 / Π(1 - x^k) を五角数定理で得た分母の逆元として求める
+/// 計算量: O(n log n)
+#[snippet(name = "special_numbers", include = "Binomial, FormalPowerSeries, taylor_shift")]
+pub fn partition<const M: u32>(n: usize) -> Vec<StaticModInt<M>> {
+  let mut f = vec![StaticModInt::new(0); n + 1];
+  f[0] = StaticModInt::new(1);
+  for k in 1.. {
+    let g1 = k * (3 * k - 1) / 2;
+    if g1 > n {
+      break;
+    }
+    let sign = if k % 2 == 1 { StaticModInt::new(-1) } else { StaticModInt::new(1) };
+    f[g1] += sign;
+    let g2 = k * (3 * k + 1) / 2;
+    if g2 <= n {
+      f[g2] += sign;
+    }
+  }
+  FormalPowerSeries::new(f).inv(n + 1).a
+}
+
+#[test]
+fn test_special_numbers_table() {
+  use super::modint::Mod;
+  let s1 = stirling1_table::<Mod>(5);
+  assert_eq!(s1[5][..=5], [0, 24, 50, 35, 10, 1].map(Mod::new));
+  let s2 = stirling2_table::<Mod>(5);
+  assert_eq!(s2[5][..=5], [0, 1, 15, 25, 10, 1].map(Mod::new));
+  assert_eq!(bell_table::<Mod>(8), [1, 1, 2, 5, 15, 52, 203, 877, 4140].map(Mod::new));
+  let b = bernoulli_table::<Mod>(8);
+  let expected = [(1, 1), (-1, 2), (1, 6), (0, 1), (-1, 30), (0, 1), (1, 42), (0, 1), (-1, 30)];
+  for (x, (p, q)) in b.into_iter().zip(expected) {
+    assert_eq!(x, Mod::new(p) / q);
+  }
+  assert_eq!(partition_table::<Mod>(10), [1, 1, 2, 3, 5, 7, 11, 15, 22, 30, 42].map(Mod::new));
+  assert_eq!(partition_table::<Mod>(100)[100], Mod::new(190569292));
+}
+
+#[test]
+fn test_special_numbers_ntt() {
+  use super::modint::Mod;
+  let n = 300;
+  let s1 = stirling1_table::<Mod>(n);
+  let s2 = stirling2_table::<Mod>(n);
+  let bells = bell_table::<Mod>(n);
+  for m in (0..=n).step_by(37).chain([1, 2, 3, n]) {
+    assert_eq!(stirling1_row::<998244353>(m), s1[m][..=m]);
+    assert_eq!(stirling2_row::<998244353>(m), s2[m][..=m]);
+    assert_eq!(bell::<998244353>(m), bells[m]);
+  }
+  assert_eq!(bernoulli::<998244353>(n), bernoulli_table::<Mod>(n));
+  assert_eq!(partition::<998244353>(1000), partition_table::<Mod>(1000));
+}
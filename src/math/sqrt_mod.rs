@@ -0,0 +1,202 @@
+use cargo_snippet::snippet;
+
+use super::modulo::{inv_mod, mul_mod, pow_mod};
+
+/// a, b < m。m >= 2^63 でも溢れないようにする
+#[snippet(name = "sqrt_mod", include = "mod_u64")]
+fn add_mod(a: u64, b: u64, m: u64) -> u64 {
+  let (s, overflow) = a.overflowing_add(b);
+  if overflow || s >= m {
+    s.wrapping_sub(m)
+  } else {
+    s
+  }
+}
+/// a, b < m
+#[snippet(name = "sqrt_mod", include = "mod_u64")]
+fn sub_mod(a: u64, b: u64, m: u64) -> u64 {
+  if a >= b {
+    a - b
+  } else {
+    a + (m - b)
+  }
+}
+
+/// Tonelli-Shanks。p - 1 = q 2^s として O(s^2 + log p)
+#[snippet(name = "sqrt_mod", include = "mod_u64")]
+fn tonelli_shanks(a: u64, p: u64, q: u64, s: u32) -> u64 {
+  let z = (2..).find(|&z| pow_mod(z, (p - 1) / 2, p) == p - 1).unwrap();
+  let mut m = s;
+  let mut c = pow_mod(z, q, p);
+  let mut t = pow_mod(a, q, p);
+  let mut r = pow_mod(a, q.div_ceil(2), p);
+  while t != 1 {
+    let mut i = 0;
+    let mut tt = t;
+    while tt != 1 {
+      tt = mul_mod(tt, tt, p);
+      i += 1;
+    }
+    let b = pow_mod(c, 1 << (m - i - 1), p);
+    m = i;
+    c = mul_mod(b, b, p);
+    t = mul_mod(t, c, p);
+    r = mul_mod(r, b, p);
+  }
+  r
+}
+
+/// Cipolla。F_p[sqrt(b^2 - a)] で (b + sqrt(b^2 - a))^((p+1)/2) を計算する。O(log p)
+#[snippet(name = "sqrt_mod", include = "mod_u64")]
+fn cipolla(a: u64, p: u64) -> u64 {
+  let (b, w) = (1..)
+    .map(|b| (b, sub_mod(mul_mod(b, b, p), a, p)))
+    .find(|&(_, w)| pow_mod(w, (p - 1) / 2, p) == p - 1)
+    .unwrap();
+  // (x0 + x1 sqrt(w)) * (y0 + y1 sqrt(w))
+  let mul = |x: (u64, u64), y: (u64, u64)| {
+    (
+      add_mod(mul_mod(x.0, y.0, p), mul_mod(mul_mod(x.1, y.1, p), w, p), p),
+      add_mod(mul_mod(x.0, y.1, p), mul_mod(x.1, y.0, p), p),
+    )
+  };
+  let mut ret = (1, 0);
+  let mut base = (b, 1);
+  let mut e = p.div_ceil(2);
+  while e > 0 {
+    if e & 1 == 1 {
+      ret = mul(ret, base);
+    }
+    base = mul(base, base);
+    e >>= 1;
+  }
+  ret.0
+}
+
+/// x^2 = a (mod p) の解 (小さい方) を返す。p は素数
+/// p - 1 の 2 べきが小さければ Tonelli-Shanks、大きければ Cipolla を使う
+/// 計算量: O(log p) 程度
+#[snippet(name = "sqrt_mod", include = "mod_u64")]
+pub fn sqrt_mod(a: u64, p: u64) -> Option<u64> {
+  let a = a % p;
+  if p == 2 || a == 0 {
+    return Some(a);
+  }
+  if pow_mod(a, (p - 1) / 2, p) != 1 {
+    return None;
+  }
+  let s = (p - 1).trailing_zeros();
+  let q = (p - 1) >> s;
+  let r = if s == 1 {
+    pow_mod(a, (p + 1) / 4, p)
+  } else if s * s <= 64 - p.leading_zeros() {
+    tonelli_shanks(a, p, q, s)
+  } else {
+    cipolla(a, p)
+  };
+  Some(r.min(p - r))
+}
+
+/// x^2 = a (mod p^e) の解を一つ返す。p は素数で p^e < 2^64
+/// p で割れない部分は Hensel 持ち上げで求める
+#[snippet(name = "sqrt_mod", include = "mod_u64")]
+pub fn sqrt_mod_prime_power(a: u64, p: u64, e: u32) -> Option<u64> {
+  let pe = p.pow(e);
+  let a = a % pe;
+  if a == 0 {
+    return Some(0);
+  }
+  // a = p^v a'
+  let mut v = 0;
+  let mut a1 = a;
+  while a1.is_multiple_of(p) {
+    a1 /= p;
+    v += 1;
+  }
+  if v % 2 == 1 {
+    return None;
+  }
+  // x = p^(v/2) y, y^2 = a' (mod p^(e - v))
+  let e1 = e - v;
+  let m = p.pow(e1);
+  let y = if p == 2 {
+    if e1 >= 2 && a1 % 4 != 1 || e1 >= 3 && a1 % 8 != 1 {
+      return None;
+    }
+    // y^2 = a' (mod 2^i) から (mod 2^(i+1)) へ
+    let mut y = 1u64;
+    for i in 3..e1 {
+      let diff = sub_mod(mul_mod(y, y, m), a1 % m, m);
+      if (diff >> i) & 1 == 1 {
+        y += 1 << (i - 1);
+      }
+    }
+    y % m
+  } else {
+    let mut y = sqrt_mod(a1, p)?;
+    // Newton 法 y <- y - (y^2 - a') / 2y
+    while mul_mod(y, y, m) != a1 % m {
+      let f = sub_mod(mul_mod(y, y, m), a1 % m, m);
+      y = sub_mod(y, mul_mod(f, inv_mod(add_mod(y, y, m), m), m), m);
+    }
+    y
+  };
+  Some(y * p.pow(v / 2) % pe)
+}
+
+#[test]
+fn test_sqrt_mod() {
+  for p in (2..500u64).filter(|&p| crate::math::prime::is_prime_u64(p)) {
+    for a in 0..p {
+      let expected = (0..p).find(|&x| x * x % p == a);
+      assert_eq!(sqrt_mod(a, p), expected, "{} {}", a, p);
+    }
+  }
+  let mut rng = crate::xorshift::Xorshift::new();
+  // s = 23 (Cipolla), s = 1, s = 2 (Tonelli-Shanks)
+  for p in [998244353, 1_000_000_007, 1_000_000_009, (1 << 61) - 1, 4611686018427387847] {
+    for _ in 0..100 {
+      let x = rng.rand(p);
+      let a = mul_mod(x, x, p);
+      assert_eq!(sqrt_mod(a, p), Some(x.min(p - x)));
+    }
+  }
+  assert_eq!(sqrt_mod(3, 998244353), None);
+  // 2^63 以上の素数 (s = 2 と s = 20)
+  for p in [18446744073709551557u64, 9223372036904058881] {
+    for _ in 0..100 {
+      let x = rng.rand(p);
+      let a = mul_mod(x, x, p);
+      assert_eq!(sqrt_mod(a, p), Some(x.min(p - x)));
+    }
+  }
+}
+
+#[test]
+fn test_sqrt_mod_prime_power() {
+  for (p, e) in [(2u64, 1), (2, 2), (2, 3), (2, 4), (2, 10), (3, 1), (3, 4), (3, 6), (5, 4), (7, 3), (11, 2)] {
+    let pe = p.pow(e);
+    for a in 0..pe {
+      let exists = (0..pe).any(|x| x * x % pe == a);
+      match sqrt_mod_prime_power(a, p, e) {
+        Some(x) => assert_eq!(x * x % pe, a, "{} {} {}", a, p, e),
+        None => assert!(!exists, "{} {} {}", a, p, e),
+      }
+    }
+  }
+  let pe = 1_000_000_007u64 * 1_000_000_007;
+  let x = 123_456_789_012_345;
+  let a = mul_mod(x, x, pe);
+  let y = sqrt_mod_prime_power(a, 1_000_000_007, 2).unwrap();
+  assert_eq!(mul_mod(y, y, pe), a);
+  // p^2 >= 2^63
+  let p = 4294967291u64;
+  let pe = p * p;
+  let mut rng = crate::xorshift::Xorshift::new();
+  for _ in 0..100 {
+    let x = rng.rand(pe);
+    let a = mul_mod(x, x, pe);
+    let y = sqrt_mod_prime_power(a, p, 2).unwrap();
+    assert_eq!(mul_mod(y, y, pe), a);
+  }
+}
@@ -0,0 +1,376 @@
+use cargo_snippet::snippet;
+use fixedbitset::FixedBitSet;
+
+/// u64 の F2 線形基底
+/// basis[i] は最上位ビットが i の基底ベクトル (なければ 0)
+#[snippet("XorBasis")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XorBasis {
+  basis: [u64; 64],
+  rank: usize,
+}
+
+#[snippet("XorBasis")]
+impl Default for XorBasis {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[snippet("XorBasis")]
+impl XorBasis {
+  pub fn new() -> Self {
+    XorBasis { basis: [0; 64], rank: 0 }
+  }
+  pub fn rank(&self) -> usize {
+    self.rank
+  }
+  /// 基底が張る空間に入っていなければ追加して true O(64)
+  pub fn insert(&mut self, x: u64) -> bool {
+    let mut x = x;
+    while x != 0 {
+      let top = 63 - x.leading_zeros() as usize;
+      if self.basis[top] == 0 {
+        self.basis[top] = x;
+        self.rank += 1;
+        return true;
+      }
+      x ^= self.basis[top];
+    }
+    false
+  }
+  /// x が基底の部分集合の xor で表せるか
+  pub fn contains(&self, x: u64) -> bool {
+    self.min_xor(x) == 0
+  }
+  /// max (x xor y) (y は張る空間の元)
+  pub fn max_xor(&self, x: u64) -> u64 {
+    (0..64).rev().fold(x, |acc, i| acc.max(acc ^ self.basis[i]))
+  }
+  /// min (x xor y) (y は張る空間の元)
+  pub fn min_xor(&self, x: u64) -> u64 {
+    (0..64).rev().fold(x, |acc, i| acc.min(acc ^ self.basis[i]))
+  }
+  /// 張る空間の元 (0 を含む 2^rank 個) のうち k 番目 (0-indexed) に小さいもの
+  pub fn kth_smallest(&self, k: u64) -> Option<u64> {
+    if self.rank < 64 && k >> self.rank != 0 {
+      return None;
+    }
+    // 簡約して各基底の最上位ビットを他の基底から消す
+    let mut reduced = self.basis;
+    for i in 0..64 {
+      if reduced[i] == 0 {
+        continue;
+      }
+      for j in i + 1..64 {
+        if reduced[j] >> i & 1 == 1 {
+          reduced[j] ^= reduced[i];
+        }
+      }
+    }
+    let mut ret = 0;
+    let mut k = k;
+    for &b in reduced.iter().filter(|&&b| b != 0) {
+      if k & 1 == 1 {
+        ret ^= b;
+      }
+      k >>= 1;
+    }
+    Some(ret)
+  }
+  /// 張る空間の和 O(64^2)
+  pub fn merge(&mut self, other: &XorBasis) {
+    for &b in other.basis.iter().filter(|&&b| b != 0) {
+      self.insert(b);
+    }
+  }
+  /// 基底ベクトルを最上位ビットの小さい順に
+  pub fn basis(&self) -> Vec<u64> {
+    self.basis.iter().copied().filter(|&b| b != 0).collect()
+  }
+}
+
+/// 長さ width のビット列の F2 線形基底
+/// MatrixBitSet の掃き出しを一本ずつ挿入できるようにしたもの
+#[snippet("XorBasisBitSet")]
+#[derive(Debug, Clone)]
+pub struct XorBasisBitSet {
+  width: usize,
+  /// basis[i] は最上位ビットが i の基底ベクトル
+  basis: Vec<Option<FixedBitSet>>,
+  rank: usize,
+}
+
+#[snippet("XorBasisBitSet")]
+fn top_bit(x: &FixedBitSet) -> Option<usize> {
+  let blocks = x.as_slice();
+  let i = blocks.iter().rposition(|&b| b != 0)?;
+  Some(i * 32 + 31 - blocks[i].leading_zeros() as usize)
+}
+
+#[snippet("XorBasisBitSet")]
+impl XorBasisBitSet {
+  pub fn new(width: usize) -> Self {
+    XorBasisBitSet {
+      width,
+      basis: vec![None; width],
+      rank: 0,
+    }
+  }
+  pub fn rank(&self) -> usize {
+    self.rank
+  }
+  /// O(width^2 / 32)
+  pub fn insert(&mut self, x: &FixedBitSet) -> bool {
+    let mut x = self.min_xor(x);
+    let Some(top) = top_bit(&x) else { return false; };
+    x.grow(self.width);
+    self.basis[top] = Some(x);
+    self.rank += 1;
+    true
+  }
+  pub fn contains(&self, x: &FixedBitSet) -> bool {
+    top_bit(&self.min_xor(x)).is_none()
+  }
+  /// 上位ビットを優先して立てる
+  pub fn max_xor(&self, x: &FixedBitSet) -> FixedBitSet {
+    let mut x = self.resized(x);
+    for (i, b) in self.basis.iter().enumerate().rev() {
+      if let Some(b) = b {
+        if !x[i] {
+          x.symmetric_difference_with(b);
+        }
+      }
+    }
+    x
+  }
+  /// 上位ビットから順に消す
+  pub fn min_xor(&self, x: &FixedBitSet) -> FixedBitSet {
+    let mut x = self.resized(x);
+    for (i, b) in self.basis.iter().enumerate().rev() {
+      if let Some(b) = b {
+        if x[i] {
+          x.symmetric_difference_with(b);
+        }
+      }
+    }
+    x
+  }
+  /// 張る空間の元のうち k 番目 (0-indexed) に小さいもの
+  pub fn kth_smallest(&self, k: u64) -> Option<FixedBitSet> {
+    if self.rank < 64 && k >> self.rank != 0 {
+      return None;
+    }
+    let mut reduced = self.basis.clone();
+    for i in 0..self.width {
+      let Some(bi) = reduced[i].clone() else { continue; };
+      for bj in reduced[i + 1..].iter_mut().flatten() {
+        if bj[i] {
+          bj.symmetric_difference_with(&bi);
+        }
+      }
+    }
+    let mut ret = FixedBitSet::with_capacity(self.width);
+    let mut k = k;
+    for b in reduced.iter().flatten() {
+      if k == 0 {
+        break;
+      }
+      if k & 1 == 1 {
+        ret.symmetric_difference_with(b);
+      }
+      k >>= 1;
+    }
+    Some(ret)
+  }
+  pub fn merge(&mut self, other: &XorBasisBitSet) {
+    for b in other.basis.iter().flatten() {
+      self.insert(b);
+    }
+  }
+  fn resized(&self, x: &FixedBitSet) -> FixedBitSet {
+    assert!(top_bit(x).is_none_or(|t| t < self.width));
+    let mut x = x.clone();
+    x.grow(self.width);
+    x
+  }
+}
+
+/// 各 prefix の基底を、なるべく右にある要素で作ったものとして持つ
+/// a[l..r] の部分集合の xor の最大値などを O(64) で答える
+/// 空間 O(64 n)
+#[snippet(name = "PrefixXorBasis", include = "XorBasis")]
+#[derive(Debug, Clone)]
+pub struct PrefixXorBasis {
+  /// snapshots[r] = a[..r] の (基底, 各基底の元になった位置)
+  snapshots: Vec<([u64; 64], [usize; 64])>,
+}
+
+#[snippet(name = "PrefixXorBasis", include = "XorBasis")]
+impl Default for PrefixXorBasis {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+#[snippet(name = "PrefixXorBasis", include = "XorBasis")]
+impl PrefixXorBasis {
+  pub fn new() -> Self {
+    PrefixXorBasis {
+      snapshots: vec![([0; 64], [0; 64])],
+    }
+  }
+  pub fn from_slice(a: &[u64]) -> Self {
+    let mut ret = Self::new();
+    for &x in a {
+      ret.push(x);
+    }
+    ret
+  }
+  pub fn len(&self) -> usize {
+    self.snapshots.len() - 1
+  }
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+  /// O(64)
+  pub fn push(&mut self, x: u64) {
+    let (mut basis, mut pos) = *self.snapshots.last().unwrap();
+    let (mut x, mut p) = (x, self.len());
+    for i in (0..64).rev() {
+      if x >> i & 1 == 0 {
+        continue;
+      }
+      if basis[i] == 0 {
+        basis[i] = x;
+        pos[i] = p;
+        break;
+      }
+      // 新しい方を基底に残す
+      if pos[i] < p {
+        std::mem::swap(&mut basis[i], &mut x);
+        std::mem::swap(&mut pos[i], &mut p);
+      }
+      x ^= basis[i];
+    }
+    self.snapshots.push((basis, pos));
+  }
+  /// a[l..r] の部分集合の xor で作れる値の基底
+  pub fn basis(&self, l: usize, r: usize) -> XorBasis {
+    assert!(l <= r && r <= self.len());
+    let (basis, pos) = &self.snapshots[r];
+    let mut ret = XorBasis::new();
+    for i in 0..64 {
+      if basis[i] != 0 && pos[i] >= l {
+        ret.basis[i] = basis[i];
+        ret.rank += 1;
+      }
+    }
+    ret
+  }
+  /// a[l..r] の部分集合の xor の最大値 (空集合は 0)
+  pub fn max_xor(&self, l: usize, r: usize) -> u64 {
+    assert!(l <= r && r <= self.len());
+    let (basis, pos) = &self.snapshots[r];
+    (0..64).rev().filter(|&i| pos[i] >= l).fold(0, |acc, i| acc.max(acc ^ basis[i]))
+  }
+}
+
+#[test]
+fn test_xor_basis() {
+  let mut rng = crate::xorshift::Xorshift::new();
+  for _ in 0..100 {
+    let n = rng.rand(8) as usize;
+    let a = (0..n).map(|_| rng.rand(64)).collect::<Vec<_>>();
+    let mut span = (0..1 << n)
+      .map(|s: usize| (0..n).filter(|&i| s >> i & 1 == 1).fold(0, |acc, i| acc ^ a[i]))
+      .collect::<Vec<_>>();
+    span.sort();
+    span.dedup();
+    let mut basis = XorBasis::new();
+    for &x in &a {
+      basis.insert(x);
+    }
+    assert_eq!(1 << basis.rank(), span.len());
+    for x in 0..64 {
+      assert_eq!(basis.contains(x), span.binary_search(&x).is_ok());
+      assert_eq!(basis.max_xor(x), span.iter().map(|&y| x ^ y).max().unwrap());
+      assert_eq!(basis.min_xor(x), span.iter().map(|&y| x ^ y).min().unwrap());
+    }
+    for (k, &x) in span.iter().enumerate() {
+      assert_eq!(basis.kth_smallest(k as u64), Some(x));
+    }
+    assert_eq!(basis.kth_smallest(span.len() as u64), None);
+
+    let mut left = XorBasis::new();
+    let mut right = XorBasis::new();
+    for (i, &x) in a.iter().enumerate() {
+      if i % 2 == 0 {
+        left.insert(x);
+      } else {
+        right.insert(x);
+      }
+    }
+    left.merge(&right);
+    assert_eq!(left.rank(), basis.rank());
+    assert!(basis.basis().iter().all(|&x| left.contains(x)));
+
+    let to_bitset = |x: u64| {
+      let mut b = FixedBitSet::with_capacity(6);
+      for i in 0..6 {
+        b.set(i, x >> i & 1 == 1);
+      }
+      b
+    };
+    let to_u64 = |b: &FixedBitSet| b.ones().fold(0, |acc, i| acc | 1 << i);
+    let mut bs = XorBasisBitSet::new(6);
+    for &x in &a {
+      bs.insert(&to_bitset(x));
+    }
+    assert_eq!(bs.rank(), basis.rank());
+    for x in 0..64 {
+      assert_eq!(bs.contains(&to_bitset(x)), basis.contains(x));
+      assert_eq!(to_u64(&bs.max_xor(&to_bitset(x))), basis.max_xor(x));
+      assert_eq!(to_u64(&bs.min_xor(&to_bitset(x))), basis.min_xor(x));
+    }
+    for k in 0..span.len() {
+      assert_eq!(bs.kth_smallest(k as u64).map(|b| to_u64(&b)), basis.kth_smallest(k as u64));
+    }
+  }
+  let mut basis = XorBasis::new();
+  assert!(basis.insert(u64::MAX));
+  assert!(basis.insert(1 << 63));
+  assert!(!basis.insert(u64::MAX >> 1));
+  assert_eq!(basis.max_xor(0), u64::MAX);
+  assert_eq!(basis.kth_smallest(3), Some(u64::MAX));
+
+  // 幅が 32 を超えるビット列
+  let mut bs = XorBasisBitSet::new(100);
+  let mut x = FixedBitSet::with_capacity(100);
+  x.insert(99);
+  x.insert(3);
+  assert!(bs.insert(&x));
+  assert!(!bs.insert(&x));
+  let mut y = FixedBitSet::with_capacity(100);
+  y.insert(3);
+  assert!(bs.max_xor(&y)[99]);
+  assert!(!bs.max_xor(&y)[3]);
+}
+
+#[test]
+fn test_prefix_xor_basis() {
+  let mut rng = crate::xorshift::Xorshift::new();
+  let a = (0..60).map(|_| rng.rand(1 << 10)).collect::<Vec<_>>();
+  let pb = PrefixXorBasis::from_slice(&a);
+  assert_eq!(pb.len(), 60);
+  for l in 0..=a.len() {
+    for r in l..=a.len() {
+      let mut basis = XorBasis::new();
+      for &x in &a[l..r] {
+        basis.insert(x);
+      }
+      assert_eq!(pb.max_xor(l, r), basis.max_xor(0));
+      assert_eq!(pb.basis(l, r).rank(), basis.rank());
+    }
+  }
+}
@@ -0,0 +1,425 @@
+use cargo_snippet::snippet;
+
+use super::ntt::{convolution, intt, ntt};
+use crate::math::modint::StaticModInt;
+use std::ops::{Add, AddAssign, Deref, DerefMut, Mul, MulAssign, Neg, Sub, SubAssign};
+
+/// 形式的冪級数。a[i] が x^i の係数
+/// 精度 n を取る演算は mod x^n で返す
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FormalPowerSeries<T> {
+  pub a: Vec<T>,
+}
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+impl<T> Deref for FormalPowerSeries<T> {
+  type Target = Vec<T>;
+  fn deref(&self) -> &Vec<T> {
+    &self.a
+  }
+}
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+impl<T> DerefMut for FormalPowerSeries<T> {
+  fn deref_mut(&mut self) -> &mut Vec<T> {
+    &mut self.a
+  }
+}
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+impl<T> From<Vec<T>> for FormalPowerSeries<T> {
+  fn from(a: Vec<T>) -> Self {
+    FormalPowerSeries { a }
+  }
+}
+
+/// 1, 1/2, ..., 1/n
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+fn inverses<const M: u32>(n: usize) -> Vec<StaticModInt<M>> {
+  let mut inv = vec![StaticModInt::raw(1); n + 1];
+  for i in 2..=n {
+    inv[i] = -inv[M as usize % i] * StaticModInt::raw(M / i as u32);
+  }
+  inv
+}
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+impl<const M: u32> FormalPowerSeries<StaticModInt<M>> {
+  pub fn new(a: Vec<StaticModInt<M>>) -> Self {
+    FormalPowerSeries { a }
+  }
+  /// mod x^n (足りない分は 0 で埋める)
+  pub fn pre(&self, n: usize) -> Self {
+    let mut a = self.a[..n.min(self.len())].to_vec();
+    a.resize(n, StaticModInt::raw(0));
+    Self::new(a)
+  }
+  pub fn derivative(&self) -> Self {
+    Self::new((1..self.len()).map(|i| self.a[i] * StaticModInt::raw(i as u32)).collect())
+  }
+  /// 定数項 0 の原始関数
+  pub fn integral(&self) -> Self {
+    let inv = inverses::<M>(self.len());
+    let mut a = vec![StaticModInt::raw(0); self.len() + 1];
+    for (i, &x) in self.a.iter().enumerate() {
+      a[i + 1] = x * inv[i + 1];
+    }
+    Self::new(a)
+  }
+  /// ニュートン法で精度を 1, 2, 4, ... と倍にしていく
+  /// step(g, m) は mod x^(m/2) で正しい g から mod x^m で正しい解を返す
+  pub fn newton(init: StaticModInt<M>, n: usize, step: impl Fn(&Self, usize) -> Self) -> Self {
+    let mut g = Self::new(vec![init]);
+    let mut m = 1;
+    while m < n {
+      m *= 2;
+      g = step(&g, m).pre(m);
+    }
+    g.pre(n)
+  }
+  /// 1 / f mod x^n (f[0] != 0)
+  /// g <- g (2 - f g) の二つの積で NTT を使い回す
+  pub fn inv(&self, n: usize) -> Self {
+    assert!(!self.is_empty() && self.a[0] != StaticModInt::raw(0));
+    Self::newton(self.a[0].inv(), n, |g, m| {
+      let half = m / 2;
+      let mut f = self.pre(m).a;
+      let mut gt = g.pre(m).a;
+      ntt(&mut f);
+      ntt(&mut gt);
+      // f g mod (x^m - 1) の x^half 以上は f g と一致し、下側は 1, 0, 0, ...
+      for (x, &y) in f.iter_mut().zip(&gt) {
+        *x *= y;
+      }
+      intt(&mut f);
+      f[..half].fill(StaticModInt::raw(0));
+      ntt(&mut f);
+      for (x, &y) in f.iter_mut().zip(&gt) {
+        *x *= y;
+      }
+      intt(&mut f);
+      let mut ret = g.pre(m);
+      for (x, &y) in ret.a[half..].iter_mut().zip(&f[half..]) {
+        *x = -y;
+      }
+      ret
+    })
+  }
+  /// log f mod x^n (f[0] = 1)
+  pub fn log(&self, n: usize) -> Self {
+    assert!(!self.is_empty() && self.a[0] == StaticModInt::raw(1));
+    if n == 0 {
+      return Self::new(vec![]);
+    }
+    let d = (&self.pre(n).derivative() * &self.inv(n)).pre(n - 1);
+    d.integral()
+  }
+  /// exp f mod x^n (f[0] = 0)
+  /// g <- g (1 - log g + f)
+  pub fn exp(&self, n: usize) -> Self {
+    assert!(self.is_empty() || self.a[0] == StaticModInt::raw(0));
+    Self::newton(StaticModInt::raw(1), n, |g, m| {
+      let mut h = &self.pre(m) - &g.log(m);
+      h.a[0] += 1;
+      &g.pre(m) * &h
+    })
+  }
+  /// f^k mod x^n
+  pub fn pow(&self, k: u64, n: usize) -> Self {
+    if k == 0 {
+      return Self::new(vec![StaticModInt::raw(1)]).pre(n);
+    }
+    let Some(i) = self.a.iter().position(|&x| x != StaticModInt::raw(0)) else {
+      return Self::new(vec![]).pre(n);
+    };
+    if i as u128 * k as u128 >= n as u128 {
+      return Self::new(vec![]).pre(n);
+    }
+    let shift = i * k as usize;
+    let c = self.a[i];
+    let c_inv = c.inv();
+    let f = Self::new(self.a[i..].iter().map(|&x| x * c_inv).collect());
+    let g = f.log(n - shift);
+    let k_mod = StaticModInt::<M>::new((k % M as u64) as i64);
+    let g = Self::new(g.a.into_iter().map(|x| x * k_mod).collect()).exp(n - shift);
+    let ck = c.pow(k);
+    let mut a = vec![StaticModInt::raw(0); shift];
+    a.extend(g.a.into_iter().map(|x| x * ck));
+    Self::new(a)
+  }
+  /// g^2 = f mod x^n となる g の一つ。存在しなければ None
+  pub fn sqrt(&self, n: usize) -> Option<Self> {
+    let Some(i) = self.a.iter().position(|&x| x != StaticModInt::raw(0)) else {
+      return Some(Self::new(vec![]).pre(n));
+    };
+    if i >= n {
+      return Some(Self::new(vec![]).pre(n));
+    }
+    if i % 2 == 1 {
+      return None;
+    }
+    let shift = i / 2;
+    let f = Self::new(self.a[i..].to_vec());
+    let root = f.a[0].sqrt()?;
+    let inv2 = StaticModInt::<M>::new(2).inv();
+    // g <- (g + f / g) / 2
+    let g = Self::newton(root, n - shift, |g, m| {
+      let h = &g.pre(m) + &(&f.pre(m) * &g.inv(m)).pre(m);
+      Self::new(h.a.into_iter().map(|x| x * inv2).collect())
+    });
+    let mut a = vec![StaticModInt::raw(0); shift];
+    a.extend(g.a);
+    Some(Self::new(a))
+  }
+  /// 末尾の 0 を取り除く
+  pub fn shrink(&mut self) {
+    while self.a.last() == Some(&StaticModInt::raw(0)) {
+      self.a.pop();
+    }
+  }
+  /// 多項式として f(x) を計算する
+  pub fn eval(&self, x: StaticModInt<M>) -> StaticModInt<M> {
+    self.a.iter().rev().fold(StaticModInt::raw(0), |acc, &c| acc * x + c)
+  }
+  /// f(g(x)) mod x^n
+  /// f を √deg f 個ずつのブロックに分け、g^0..g^k と G = g^k の冪で組み立てる O(n^2 + n√n log n)
+  pub fn compose(&self, g: &Self, n: usize) -> Self {
+    if n == 0 || self.is_empty() {
+      return Self::new(vec![]).pre(n);
+    }
+    let k = ((self.len() as f64).sqrt().ceil() as usize).max(1);
+    let mut pows = vec![Self::new(vec![StaticModInt::raw(1)]).pre(n)];
+    for i in 0..k {
+      let next = (&pows[i] * &g.pre(n)).pre(n);
+      pows.push(next);
+    }
+    let big = pows.pop().unwrap();
+    let mut ret = Self::new(vec![]).pre(n);
+    for block in self.a.chunks(k).rev() {
+      let mut inner = vec![StaticModInt::raw(0); n];
+      for (&c, p) in block.iter().zip(&pows) {
+        for (x, &y) in inner.iter_mut().zip(&p.a) {
+          *x += c * y;
+        }
+      }
+      // ret <- ret * G + inner
+      ret = &(&ret * &big).pre(n) + &Self::new(inner);
+    }
+    ret
+  }
+  /// 多項式としての商と余り (f = q g + r, deg r < deg g)
+  /// 反転して g の逆元を掛ける O(n log n)
+  pub fn div_rem(&self, g: &Self) -> (Self, Self) {
+    let mut f = self.clone();
+    f.shrink();
+    let mut g = g.clone();
+    g.shrink();
+    assert!(!g.is_empty(), "division by zero polynomial");
+    if f.len() < g.len() {
+      return (Self::new(vec![]), f);
+    }
+    let k = f.len() - g.len() + 1;
+    let rf = Self::new(f.a.iter().rev().take(k).copied().collect());
+    let rg = Self::new(g.a.iter().rev().take(k).copied().collect());
+    let mut q = (&rf * &rg.inv(k)).pre(k);
+    q.a.reverse();
+    let mut r = (&f - &(&q * &g)).pre(g.len() - 1);
+    r.shrink();
+    (q, r)
+  }
+}
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+impl<const M: u32> Add for &FormalPowerSeries<StaticModInt<M>> {
+  type Output = FormalPowerSeries<StaticModInt<M>>;
+  fn add(self, rhs: Self) -> Self::Output {
+    let mut ret = self.pre(self.len().max(rhs.len()));
+    for (x, &y) in ret.a.iter_mut().zip(&rhs.a) {
+      *x += y;
+    }
+    ret
+  }
+}
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+impl<const M: u32> Sub for &FormalPowerSeries<StaticModInt<M>> {
+  type Output = FormalPowerSeries<StaticModInt<M>>;
+  fn sub(self, rhs: Self) -> Self::Output {
+    let mut ret = self.pre(self.len().max(rhs.len()));
+    for (x, &y) in ret.a.iter_mut().zip(&rhs.a) {
+      *x -= y;
+    }
+    ret
+  }
+}
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+impl<const M: u32> Mul for &FormalPowerSeries<StaticModInt<M>> {
+  type Output = FormalPowerSeries<StaticModInt<M>>;
+  fn mul(self, rhs: Self) -> Self::Output {
+    FormalPowerSeries::new(convolution(&self.a, &rhs.a))
+  }
+}
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+impl<const M: u32> Neg for &FormalPowerSeries<StaticModInt<M>> {
+  type Output = FormalPowerSeries<StaticModInt<M>>;
+  fn neg(self) -> Self::Output {
+    FormalPowerSeries::new(self.a.iter().map(|&x| -x).collect())
+  }
+}
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+macro_rules! fps_owned_ops {
+  ($($tr:ident $f:ident $tra:ident $fa:ident),*) => {
+    $(
+      impl<const M: u32> $tr for FormalPowerSeries<StaticModInt<M>> {
+        type Output = Self;
+        fn $f(self, rhs: Self) -> Self {
+          (&self).$f(&rhs)
+        }
+      }
+      impl<const M: u32> $tra for FormalPowerSeries<StaticModInt<M>> {
+        fn $fa(&mut self, rhs: Self) {
+          *self = (&*self).$f(&rhs);
+        }
+      }
+    )*
+  };
+}
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+fps_owned_ops!(Add add AddAssign add_assign, Sub sub SubAssign sub_assign, Mul mul MulAssign mul_assign);
+
+#[snippet(name = "FormalPowerSeries", include = "convolution, modint_sqrt")]
+impl<const M: u32> Neg for FormalPowerSeries<StaticModInt<M>> {
+  type Output = Self;
+  fn neg(self) -> Self {
+    -&self
+  }
+}
+
+#[test]
+fn test_fps_arithmetic() {
+  use crate::math::modint::Mod;
+  let f = FormalPowerSeries::new(vec![Mod::new(1), Mod::new(2)]);
+  let g = FormalPowerSeries::new(vec![Mod::new(3), Mod::new(4), Mod::new(5)]);
+  assert_eq!((&f + &g).a, vec![Mod::new(4), Mod::new(6), Mod::new(5)]);
+  assert_eq!((&f - &g).a, vec![Mod::new(-2), Mod::new(-2), Mod::new(-5)]);
+  assert_eq!((f.clone() * g.clone()).a, vec![Mod::new(3), Mod::new(10), Mod::new(13), Mod::new(10)]);
+  assert_eq!((-f.clone()).a, vec![Mod::new(-1), Mod::new(-2)]);
+  assert_eq!(g.derivative().a, vec![Mod::new(4), Mod::new(10)]);
+  assert_eq!(g.derivative().integral().a, vec![Mod::new(0), Mod::new(4), Mod::new(5)]);
+  // 1 / (1 - x) = 1 + x + x^2 + ...
+  let h = FormalPowerSeries::new(vec![Mod::new(1), Mod::new(-1)]);
+  assert_eq!(h.inv(5).a, vec![Mod::new(1); 5]);
+}
+
+#[test]
+fn test_fps_inv_log_exp() {
+  use crate::math::modint::Mod;
+  let mut rng = crate::xorshift::Xorshift::new();
+  for n in [1, 2, 3, 10, 64, 100, 300] {
+    let mut f = FormalPowerSeries::new((0..n).map(|_| Mod::new(rng.rand(998244353) as i64)).collect());
+    f[0] = Mod::new(1);
+    let g = f.inv(n);
+    assert_eq!(g.len(), n);
+    assert_eq!((&f * &g).pre(n), FormalPowerSeries::new(vec![Mod::new(1)]).pre(n));
+    let l = f.log(n);
+    assert_eq!(l[0], Mod::new(0));
+    assert_eq!(l.exp(n), f);
+    // f^k と掛け算の比較
+    let k = rng.rand(5) + 2;
+    let mut p = FormalPowerSeries::new(vec![Mod::new(1)]);
+    for _ in 0..k {
+      p = (&p * &f).pre(n);
+    }
+    assert_eq!(f.pow(k, n), p);
+  }
+  // exp(x) = Σ x^k / k!
+  let e = FormalPowerSeries::new(vec![Mod::new(0), Mod::new(1)]).exp(6);
+  assert_eq!(e[5], Mod::new(120).inv());
+}
+
+#[test]
+fn test_fps_pow_sqrt() {
+  use crate::math::modint::Mod;
+  // 先頭に 0 がある場合
+  let f = FormalPowerSeries::new(vec![Mod::new(0), Mod::new(0), Mod::new(2), Mod::new(1)]);
+  let mut p = FormalPowerSeries::new(vec![Mod::new(1)]);
+  for k in 0..6 {
+    assert_eq!(f.pow(k, 10), p.pre(10));
+    p = &p * &f;
+  }
+  assert_eq!(f.pow(5, 10), FormalPowerSeries::new(vec![Mod::new(0); 10]));
+  assert_eq!(f.pow(1 << 62, 10), FormalPowerSeries::new(vec![Mod::new(0); 10]));
+  assert_eq!(FormalPowerSeries::<Mod>::new(vec![]).pow(0, 3).a, vec![Mod::new(1), Mod::new(0), Mod::new(0)]);
+
+  let mut rng = crate::xorshift::Xorshift::new();
+  for n in [1, 5, 40, 200] {
+    for shift in [0, 2, 6] {
+      let mut g = FormalPowerSeries::new((0..n).map(|_| Mod::new(rng.rand(998244353) as i64)).collect());
+      g[0] = Mod::new(rng.rand(998244352) as i64 + 1);
+      let mut sq = vec![Mod::new(0); shift];
+      sq.extend((&g * &g).a);
+      let f = FormalPowerSeries::new(sq).pre(n);
+      let h = f.sqrt(n).unwrap();
+      assert_eq!((&h * &h).pre(n), f);
+    }
+  }
+  assert!(FormalPowerSeries::new(vec![Mod::new(0), Mod::new(1)]).sqrt(3).is_none());
+  // 5 は mod 998244353 で平方非剰余
+  assert!(FormalPowerSeries::new(vec![Mod::new(5)]).sqrt(3).is_none());
+  assert_eq!(FormalPowerSeries::new(vec![Mod::new(0), Mod::new(1)]).sqrt(1).unwrap().a, vec![Mod::new(0)]);
+}
+
+#[test]
+fn test_fps_div_rem() {
+  use crate::math::modint::Mod;
+  let mut rng = crate::xorshift::Xorshift::new();
+  for (n, m) in [(1, 1), (3, 5), (10, 3), (100, 40), (300, 1)] {
+    let f = FormalPowerSeries::new((0..n).map(|_| Mod::new(rng.rand(998244353) as i64)).collect());
+    let mut g = FormalPowerSeries::new((0..m).map(|_| Mod::new(rng.rand(998244353) as i64)).collect());
+    g[m - 1] = Mod::new(rng.rand(998244352) as i64 + 1);
+    let (q, r) = f.div_rem(&g);
+    assert!(r.len() < m);
+    let mut back = &(&q * &g) + &r;
+    back.shrink();
+    let mut f = f.clone();
+    f.shrink();
+    assert_eq!(back, f);
+    let x = Mod::new(rng.rand(998244353) as i64);
+    assert_eq!(f.eval(x), q.eval(x) * g.eval(x) + r.eval(x));
+  }
+  // x^2 - 1 = (x + 1)(x - 1)
+  let f = FormalPowerSeries::new(vec![Mod::new(-1), Mod::new(0), Mod::new(1)]);
+  let g = FormalPowerSeries::new(vec![Mod::new(-1), Mod::new(1), Mod::new(0)]);
+  let (q, r) = f.div_rem(&g);
+  assert_eq!(q.a, vec![Mod::new(1), Mod::new(1)]);
+  assert!(r.is_empty());
+}
+
+#[test]
+fn test_fps_compose() {
+  use crate::math::modint::Mod;
+  let mut rng = crate::xorshift::Xorshift::new();
+  for (nf, ng, n) in [(1, 1, 1), (3, 2, 5), (10, 10, 10), (50, 30, 70), (100, 1, 100)] {
+    let f = FormalPowerSeries::new((0..nf).map(|_| Mod::new(rng.rand(998244353) as i64)).collect());
+    let g = FormalPowerSeries::new((0..ng).map(|_| Mod::new(rng.rand(998244353) as i64)).collect());
+    // ホーナー法で愚直に
+    let mut expected = FormalPowerSeries::new(vec![]).pre(n);
+    for &c in f.iter().rev() {
+      expected = (&expected * &g).pre(n);
+      expected[0] += c;
+    }
+    assert_eq!(f.compose(&g, n), expected);
+  }
+  // exp(x) と log(1 + x) の合成は 1 + x
+  let n = 20;
+  let e = FormalPowerSeries::new(vec![Mod::new(0), Mod::new(1)]).exp(n);
+  let l = FormalPowerSeries::new(vec![Mod::new(1), Mod::new(1)]).log(n);
+  let mut one_plus_x = vec![Mod::new(0); n];
+  one_plus_x[0] = Mod::new(1);
+  one_plus_x[1] = Mod::new(1);
+  assert_eq!(e.compose(&l, n).a, one_plus_x);
+}
@@ -0,0 +1,137 @@
+use cargo_snippet::snippet;
+
+use super::fps::FormalPowerSeries;
+use crate::math::combination::Binomial;
+use crate::math::modint::{ModInt, StaticModInt};
+
+#[snippet(name = "interpolation", include = "FormalPowerSeries")]
+type Poly<const M: u32> = FormalPowerSeries<StaticModInt<M>>;
+
+/// 部分積木。tree[k] は葉 k の区間の Π(x - x_i)
+#[snippet(name = "interpolation", include = "FormalPowerSeries")]
+struct SubproductTree<const M: u32> {
+  size: usize,
+  tree: Vec<Poly<M>>,
+}
+
+#[snippet(name = "interpolation", include = "FormalPowerSeries")]
+impl<const M: u32> SubproductTree<M> {
+  fn new(xs: &[StaticModInt<M>]) -> Self {
+    let size = xs.len().next_power_of_two();
+    let mut tree = vec![Poly::new(vec![StaticModInt::raw(1)]); 2 * size];
+    for (i, &x) in xs.iter().enumerate() {
+      tree[size + i] = Poly::new(vec![-x, StaticModInt::raw(1)]);
+    }
+    for k in (1..size).rev() {
+      tree[k] = &tree[2 * k] * &tree[2 * k + 1];
+    }
+    SubproductTree { size, tree }
+  }
+}
+
+/// 多項式 f の xs での値 O(n log^2 n)
+#[snippet(name = "interpolation", include = "FormalPowerSeries")]
+pub fn multipoint_evaluation<const M: u32>(f: &[StaticModInt<M>], xs: &[StaticModInt<M>]) -> Vec<StaticModInt<M>> {
+  if xs.is_empty() {
+    return vec![];
+  }
+  let st = SubproductTree::new(xs);
+  let mut rem = vec![Poly::new(vec![]); 2 * st.size];
+  rem[1] = Poly::new(f.to_vec()).div_rem(&st.tree[1]).1;
+  for k in 2..st.size + xs.len() {
+    rem[k] = rem[k / 2].div_rem(&st.tree[k]).1;
+  }
+  (0..xs.len())
+    .map(|i| rem[st.size + i].first().copied().unwrap_or(StaticModInt::raw(0)))
+    .collect()
+}
+
+/// f(xs[i]) = ys[i] となる次数 n - 1 以下の多項式 (xs は相異なる) O(n log^2 n)
+#[snippet(name = "interpolation", include = "FormalPowerSeries")]
+pub fn interpolation<const M: u32>(xs: &[StaticModInt<M>], ys: &[StaticModInt<M>]) -> Vec<StaticModInt<M>> {
+  assert_eq!(xs.len(), ys.len());
+  let n = xs.len();
+  if n == 0 {
+    return vec![];
+  }
+  let st = SubproductTree::new(xs);
+  // f = Σ ys[i] / M'(xs[i]) * M(x) / (x - xs[i])
+  let dm = multipoint_evaluation(&st.tree[1].derivative(), xs);
+  let mut sum = vec![Poly::new(vec![]); 2 * st.size];
+  for i in 0..n {
+    sum[st.size + i] = Poly::new(vec![ys[i] / dm[i]]);
+  }
+  for k in (1..st.size).rev() {
+    sum[k] = &(&sum[2 * k] * &st.tree[2 * k + 1]) + &(&sum[2 * k + 1] * &st.tree[2 * k]);
+  }
+  let mut f = sum.swap_remove(1);
+  f.resize(n, StaticModInt::raw(0));
+  f.a
+}
+
+/// 0, 1, ..., n での値 ys から n 次以下の多項式の x での値を求める O(n)
+#[snippet(name = "interpolate_consecutive", include = "Binomial")]
+pub fn interpolate_consecutive<T: ModInt>(ys: &[T], x: T) -> T {
+  let n = ys.len();
+  if n == 0 {
+    return T::default();
+  }
+  let mut binom = Binomial::<T>::new(n);
+  // pre[i] = Π_{j<i} (x - j), suf[i] = Π_{j>i} (x - j)
+  let mut pre = vec![T::from(1); n + 1];
+  for i in 0..n {
+    pre[i + 1] = pre[i] * (x - T::from(i as i64));
+  }
+  let mut suf = T::from(1);
+  let mut res = T::default();
+  for i in (0..n).rev() {
+    let mut term = ys[i] * pre[i] * suf * binom.inv_fact(i) * binom.inv_fact(n - 1 - i);
+    if (n - 1 - i) % 2 == 1 {
+      term = -term;
+    }
+    res += term;
+    suf *= x - T::from(i as i64);
+  }
+  res
+}
+
+#[test]
+fn test_multipoint_evaluation() {
+  use crate::math::modint::Mod;
+  let mut rng = crate::xorshift::Xorshift::new();
+  for (n, m) in [(0, 3), (1, 1), (5, 1), (10, 17), (100, 100), (40, 300)] {
+    let f = (0..n).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+    let xs = (0..m).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+    let fp = Poly::new(f.clone());
+    let expected = xs.iter().map(|&x| fp.eval(x)).collect::<Vec<_>>();
+    assert_eq!(multipoint_evaluation(&f, &xs), expected);
+  }
+}
+
+#[test]
+fn test_interpolation() {
+  use crate::math::modint::Mod;
+  let mut rng = crate::xorshift::Xorshift::new();
+  for n in [1, 2, 7, 64, 150] {
+    let f = (0..n).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+    let mut xs = (0..n).map(|_| rng.rand(998244353) as i64).collect::<Vec<_>>();
+    xs.sort();
+    xs.dedup();
+    let xs = xs.into_iter().map(Mod::new).collect::<Vec<_>>();
+    if xs.len() < n {
+      continue;
+    }
+    let fp = Poly::new(f.clone());
+    let ys = xs.iter().map(|&x| fp.eval(x)).collect::<Vec<_>>();
+    assert_eq!(interpolation(&xs, &ys), f);
+  }
+
+  // Σ_{i=1}^{x} i^3 は 4 次式
+  let ys = (0..=4).map(|j: i64| Mod::new((1..=j).map(|i| i * i * i).sum())).collect::<Vec<_>>();
+  let x = 1_000_000_000_000i64;
+  let x_mod = Mod::new(x);
+  let expected = x_mod * x_mod * (x_mod + 1) * (x_mod + 1) / 4;
+  assert_eq!(interpolate_consecutive(&ys, x_mod), expected);
+  assert_eq!(interpolate_consecutive(&ys, Mod::new(3)), Mod::new(36));
+  assert_eq!(interpolate_consecutive(&[Mod::new(5)], Mod::new(100)), Mod::new(5));
+}
@@ -0,0 +1,141 @@
+use cargo_snippet::snippet;
+
+use super::ntt::convolution;
+use crate::math::modint::{ModInt, StaticModInt};
+
+/// Berlekamp–Massey
+/// s[i] = Σ_{j=1}^{d} c[j-1] * s[i-j] (i >= d) を満たす最短の c を返す O(n^2)
+/// 長さ 2d 以上の prefix があれば d 項間漸化式を復元できる
+#[snippet(name = "linear_recurrence", include = "convolution")]
+pub fn berlekamp_massey<T: ModInt>(s: &[T]) -> Vec<T> {
+  let zero = T::default();
+  let one = T::from(1);
+  // c(x) = 1 - Σ c_j x^j の形で持つ
+  let mut c = vec![one];
+  let mut b = vec![one];
+  let mut len = 0;
+  let mut shift = 1;
+  let mut last = one;
+  for i in 0..s.len() {
+    let d = c.iter().take(len + 1).enumerate().fold(zero, |acc, (j, &x)| acc + x * s[i - j]);
+    if d == zero {
+      shift += 1;
+      continue;
+    }
+    let coef = d * last.inv();
+    let prev = c.clone();
+    if c.len() < b.len() + shift {
+      c.resize(b.len() + shift, zero);
+    }
+    for (j, &x) in b.iter().enumerate() {
+      c[j + shift] -= coef * x;
+    }
+    if 2 * len <= i {
+      len = i + 1 - len;
+      b = prev;
+      last = d;
+      shift = 1;
+    } else {
+      shift += 1;
+    }
+  }
+  c.resize(len + 1, zero);
+  c[1..].iter().map(|&x| -x).collect()
+}
+
+/// Bostan–Mori
+/// [x^k] p(x) / q(x) (q[0] != 0, deg p < deg q) O(d log d log k)
+#[snippet(name = "linear_recurrence", include = "convolution")]
+pub fn bostan_mori<const M: u32>(p: &[StaticModInt<M>], q: &[StaticModInt<M>], k: u64) -> StaticModInt<M> {
+  assert!(!q.is_empty() && q[0] != StaticModInt::raw(0));
+  let mut p = p.to_vec();
+  let mut q = q.to_vec();
+  let mut k = k;
+  while k > 0 {
+    // q(-x)
+    let q_neg = q
+      .iter()
+      .enumerate()
+      .map(|(i, &x)| if i % 2 == 0 { x } else { -x })
+      .collect::<Vec<_>>();
+    let u = convolution(&p, &q_neg);
+    let v = convolution(&q, &q_neg);
+    let parity = (k % 2) as usize;
+    p = u.into_iter().skip(parity).step_by(2).collect();
+    q = v.into_iter().step_by(2).collect();
+    k /= 2;
+  }
+  if p.is_empty() {
+    StaticModInt::raw(0)
+  } else {
+    p[0] * q[0].inv()
+  }
+}
+
+/// a[i] = Σ_{j=1}^{d} c[j-1] * a[i-j] で定まる数列の第 k 項 (0-indexed)
+/// a は先頭 d 項 O(d log d log k)
+#[snippet(name = "linear_recurrence", include = "convolution")]
+pub fn linear_recurrence_kth<const M: u32>(a: &[StaticModInt<M>], c: &[StaticModInt<M>], k: u64) -> StaticModInt<M> {
+  let d = c.len();
+  assert!(a.len() >= d);
+  if k < a.len() as u64 {
+    return a[k as usize];
+  }
+  if d == 0 {
+    return StaticModInt::raw(0);
+  }
+  let mut q = vec![StaticModInt::raw(1)];
+  q.extend(c.iter().map(|&x| -x));
+  let mut p = convolution(&a[..d], &q);
+  p.truncate(d);
+  bostan_mori(&p, &q, k)
+}
+
+/// 数列の先頭から最短の漸化式を推定して第 k 項を求める
+#[snippet(name = "linear_recurrence", include = "convolution")]
+pub fn guess_kth_term<const M: u32>(s: &[StaticModInt<M>], k: u64) -> StaticModInt<M> {
+  let c = berlekamp_massey(s);
+  linear_recurrence_kth(s, &c, k)
+}
+
+#[test]
+fn test_berlekamp_massey() {
+  use crate::math::modint::Mod;
+  let fib = (0..20)
+    .scan((Mod::new(0), Mod::new(1)), |st, _| {
+      let ret = st.0;
+      *st = (st.1, st.0 + st.1);
+      Some(ret)
+    })
+    .collect::<Vec<_>>();
+  assert_eq!(berlekamp_massey(&fib), vec![Mod::new(1), Mod::new(1)]);
+  assert_eq!(berlekamp_massey(&[Mod::new(0); 5]), vec![]);
+  assert_eq!(berlekamp_massey(&[Mod::new(2), Mod::new(6), Mod::new(18), Mod::new(54)]), vec![Mod::new(3)]);
+
+  let mut rng = crate::xorshift::Xorshift::new();
+  for d in [1, 2, 5, 40] {
+    let c = (0..d).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+    let mut s = (0..d).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+    for i in d..3 * d + 10 {
+      let x = (0..d).map(|j| c[j] * s[i - 1 - j]).sum::<Mod>();
+      s.push(x);
+    }
+    assert_eq!(berlekamp_massey(&s[..2 * d]), c);
+    for k in 0..s.len() {
+      assert_eq!(linear_recurrence_kth(&s[..d], &c, k as u64), s[k]);
+      assert_eq!(guess_kth_term(&s[..2 * d], k as u64), s[k]);
+    }
+  }
+}
+
+#[test]
+fn test_bostan_mori() {
+  use crate::math::modint::Mod;
+  let fib = [Mod::new(0), Mod::new(1)];
+  let c = [Mod::new(1), Mod::new(1)];
+  assert_eq!(linear_recurrence_kth(&fib, &c, 100), Mod::new(494958974));
+  assert_eq!(linear_recurrence_kth(&fib, &c, 1_000_000_000_000_000_000), Mod::new(23849548));
+  // 1 / (1 - x)^2 = Σ (k + 1) x^k
+  let q = [Mod::new(1), Mod::new(-2), Mod::new(1)];
+  assert_eq!(bostan_mori(&[Mod::new(1)], &q, 1_000_000), Mod::new(1_000_001));
+}
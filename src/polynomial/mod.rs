@@ -0,0 +1,5 @@
+pub mod fps;
+pub mod interpolation;
+pub mod linear_recurrence;
+pub mod ntt;
+pub mod shift;
@@ -1,18 +1,6 @@
-fn pow(x: i64, n: i64) -> i64 {
-  let mut ret = 1;
-  let mut m = n;
-  let mut y = x;
-  while m > 0 {
-    if 1 & m == 1 {
-      ret *= y;
-      ret %= MOD;
-    }
-    y *= y;
-    y %= MOD;
-    m /= 2;
-  }
-  ret % MOD
-}
+use cargo_snippet::snippet;
+
+use crate::math::modint::StaticModInt;
 
 #[test]
 fn test_ntt() {
@@ -241,3 +229,226 @@ pub fn ntt_multiply(a: &[i64], b: &[i64], mo: i64) -> Vec<i64> {
   res.truncate(n + m - 1);
   res
 }
+
+/// StaticModInt 上の NTT (in-place)。a.len() は 2 冪、M は原始根 3 の NTT 素数
+/// 結果は通常の順序で返す
+#[snippet(name = "convolution", include = "modint")]
+pub fn ntt<const M: u32>(a: &mut [StaticModInt<M>]) {
+  butterfly(a, false);
+}
+
+/// ntt の逆変換 (1/n 倍まで含む)
+#[snippet(name = "convolution", include = "modint")]
+pub fn intt<const M: u32>(a: &mut [StaticModInt<M>]) {
+  butterfly(a, true);
+  let n_inv = StaticModInt::<M>::new(a.len() as i64).inv();
+  for x in a.iter_mut() {
+    *x *= n_inv;
+  }
+}
+
+#[snippet(name = "convolution", include = "modint")]
+fn butterfly<const M: u32>(a: &mut [StaticModInt<M>], inverse: bool) {
+  let n = a.len();
+  assert!(n.is_power_of_two() && ((M - 1) as usize).is_multiple_of(n));
+  let mut j = 0;
+  for i in 1..n {
+    let mut bit = n >> 1;
+    while j & bit != 0 {
+      j ^= bit;
+      bit >>= 1;
+    }
+    j ^= bit;
+    if i < j {
+      a.swap(i, j);
+    }
+  }
+  let g = StaticModInt::<M>::raw(3);
+  let mut len = 2;
+  while len <= n {
+    let mut w = g.pow(((M - 1) as usize / len) as u64);
+    if inverse {
+      w = w.inv();
+    }
+    let half = len / 2;
+    let mut ws = Vec::with_capacity(half);
+    let mut wk = StaticModInt::raw(1);
+    for _ in 0..half {
+      ws.push(wk);
+      wk *= w;
+    }
+    for chunk in a.chunks_mut(len) {
+      let (lo, hi) = chunk.split_at_mut(half);
+      for ((x, y), &wk) in lo.iter_mut().zip(hi.iter_mut()).zip(&ws) {
+        let u = *x;
+        let v = *y * wk;
+        *x = u + v;
+        *y = u - v;
+      }
+    }
+    len <<= 1;
+  }
+}
+
+/// StaticModInt の列の畳み込み。M は原始根 3 の NTT 素数 (998244353 など)
+/// 長さ a.len() + b.len() - 1 で返す
+#[snippet(name = "convolution", include = "modint")]
+pub fn convolution<const M: u32>(a: &[StaticModInt<M>], b: &[StaticModInt<M>]) -> Vec<StaticModInt<M>> {
+  if a.is_empty() || b.is_empty() {
+    return vec![];
+  }
+  let len = a.len() + b.len() - 1;
+  if a.len().min(b.len()) <= 32 {
+    let mut c = vec![StaticModInt::raw(0); len];
+    for (i, &x) in a.iter().enumerate() {
+      for (j, &y) in b.iter().enumerate() {
+        c[i + j] += x * y;
+      }
+    }
+    return c;
+  }
+  let n = len.next_power_of_two();
+  let mut fa = a.to_vec();
+  let mut fb = b.to_vec();
+  fa.resize(n, StaticModInt::raw(0));
+  fb.resize(n, StaticModInt::raw(0));
+  ntt(&mut fa);
+  ntt(&mut fb);
+  for (x, &y) in fa.iter_mut().zip(&fb) {
+    *x *= y;
+  }
+  intt(&mut fa);
+  fa.truncate(len);
+  fa
+}
+
+#[snippet(name = "convolution_arbitrary_mod", include = "convolution")]
+const P1: u32 = 167772161;
+#[snippet(name = "convolution_arbitrary_mod", include = "convolution")]
+const P2: u32 = 469762049;
+#[snippet(name = "convolution_arbitrary_mod", include = "convolution")]
+const P3: u32 = 998244353;
+
+/// 3 つの NTT 素数で畳み込んで CRT で復元する
+/// 各係数の真の値が P1 P2 P3 (約 7.8 * 10^25) 未満なら正確
+/// 結果の長さ a.len() + b.len() - 1 は P3 = 998244353 で NTT できる 2^23 以下
+#[snippet(name = "convolution_arbitrary_mod", include = "convolution")]
+fn convolution_three_primes(a: &[u64], b: &[u64]) -> Vec<u128> {
+  assert!(
+    (a.len() + b.len()).saturating_sub(1) <= 1 << 23,
+    "convolution length exceeds 2^23"
+  );
+  fn conv<const P: u32>(a: &[u64], b: &[u64]) -> Vec<StaticModInt<P>> {
+    let a = a.iter().map(|&x| StaticModInt::raw((x % P as u64) as u32)).collect::<Vec<_>>();
+    let b = b.iter().map(|&x| StaticModInt::raw((x % P as u64) as u32)).collect::<Vec<_>>();
+    convolution(&a, &b)
+  }
+  let x = conv::<P1>(a, b);
+  let y = conv::<P2>(a, b);
+  let z = conv::<P3>(a, b);
+  let p1_inv = StaticModInt::<P2>::raw(P1).inv();
+  let p12_inv = (StaticModInt::<P3>::raw(P1) * StaticModInt::<P3>::raw(P2)).inv();
+  let p12 = P1 as u128 * P2 as u128;
+  x.iter()
+    .zip(&y)
+    .zip(&z)
+    .map(|((&x, &y), &z)| {
+      // v = x + t1 P1 + t2 P1 P2
+      let t1 = (y - StaticModInt::raw(x.val() % P2)) * p1_inv;
+      let v12 = x.val() as u128 + t1.val() as u128 * P1 as u128;
+      let t2 = (z - StaticModInt::raw((v12 % P3 as u128) as u32)) * p12_inv;
+      v12 + t2.val() as u128 * p12
+    })
+    .collect()
+}
+
+/// 任意の mod m での畳み込み (1 <= m < 2^31)
+/// a.len() + b.len() - 1 <= 2^23 まで
+#[snippet(name = "convolution_arbitrary_mod", include = "convolution")]
+pub fn convolution_arbitrary_mod(a: &[u64], b: &[u64], m: u64) -> Vec<u64> {
+  assert!((1..1 << 31).contains(&m));
+  let a = a.iter().map(|&x| x % m).collect::<Vec<_>>();
+  let b = b.iter().map(|&x| x % m).collect::<Vec<_>>();
+  convolution_three_primes(&a, &b)
+    .into_iter()
+    .map(|x| (x % m as u128) as u64)
+    .collect()
+}
+
+/// 整数としての畳み込み
+/// 結果の各係数が P1 P2 P3 (約 7.8 * 10^25) 未満である必要がある
+/// a.len() + b.len() - 1 <= 2^23 まで
+#[snippet(name = "convolution_arbitrary_mod", include = "convolution")]
+pub fn convolution_u128(a: &[u64], b: &[u64]) -> Vec<u128> {
+  convolution_three_primes(a, b)
+}
+
+#[test]
+fn test_convolution() {
+  use crate::math::modint::Mod;
+  let mut rng = crate::xorshift::Xorshift::new();
+  for (n, m) in [(1, 1), (3, 100), (100, 3), (50, 70), (257, 300)] {
+    let a = (0..n).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+    let b = (0..m).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+    let mut expected = vec![Mod::new(0); n + m - 1];
+    for i in 0..n {
+      for j in 0..m {
+        expected[i + j] += a[i] * b[j];
+      }
+    }
+    assert_eq!(convolution(&a, &b), expected);
+  }
+  assert_eq!(convolution::<998244353>(&[], &[Mod::new(1)]), vec![]);
+
+  let a = (0..64).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+  let mut b = a.clone();
+  ntt(&mut b);
+  // 長さ 64 の DFT の定義通り
+  let w = Mod::new(3).pow((998244353 - 1) / 64);
+  for k in [0, 1, 5, 63] {
+    let expected = (0..64).map(|j| a[j] * w.pow((j * k) as u64)).sum::<Mod>();
+    assert_eq!(b[k], expected);
+  }
+  intt(&mut b);
+  assert_eq!(a, b);
+}
+
+#[test]
+fn test_convolution_arbitrary_mod() {
+  let mut rng = crate::xorshift::Xorshift::new();
+  for &m in &[1u64, 2, 1_000_000_007, 1_000_000_009, (1 << 31) - 1, 998244353] {
+    for &(n, k) in &[(1, 1), (5, 40), (100, 100), (1000, 777)] {
+      let a = (0..n).map(|_| rng.rand(m)).collect::<Vec<_>>();
+      let b = (0..k).map(|_| rng.rand(m)).collect::<Vec<_>>();
+      let a_i64 = a.iter().map(|&x| x as i64).collect::<Vec<_>>();
+      let b_i64 = b.iter().map(|&x| x as i64).collect::<Vec<_>>();
+      let expected = ntt_multiply_naive(&a_i64, &b_i64, m as i64).into_iter().map(|x| x as u64).collect::<Vec<_>>();
+      assert_eq!(convolution_arbitrary_mod(&a, &b, m), expected);
+    }
+  }
+  // 入力が m 以上でもよい
+  assert_eq!(convolution_arbitrary_mod(&[10, 11], &[12], 7), vec![1, 6]);
+  assert_eq!(convolution_arbitrary_mod(&[], &[1], 7), vec![]);
+}
+
+#[test]
+#[should_panic(expected = "convolution length exceeds 2^23")]
+fn test_convolution_arbitrary_mod_too_long() {
+  convolution_arbitrary_mod(&vec![1; (1 << 22) + 1], &vec![1; (1 << 22) + 1], 1_000_000_007);
+}
+
+#[test]
+fn test_convolution_u128() {
+  let mut rng = crate::xorshift::Xorshift::new();
+  for &(n, k) in &[(1, 1), (3, 70), (500, 300)] {
+    let a = (0..n).map(|_| rng.rand(1 << 36)).collect::<Vec<_>>();
+    let b = (0..k).map(|_| rng.rand(1 << 36)).collect::<Vec<_>>();
+    let mut expected = vec![0u128; n + k - 1];
+    for i in 0..n {
+      for j in 0..k {
+        expected[i + j] += a[i] as u128 * b[j] as u128;
+      }
+    }
+    assert_eq!(convolution_u128(&a, &b), expected);
+  }
+}
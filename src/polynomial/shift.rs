@@ -0,0 +1,138 @@
+use cargo_snippet::snippet;
+
+use super::ntt::convolution;
+use crate::math::combination::Binomial;
+use crate::math::modint::StaticModInt;
+
+/// f(x + c) O(n log n)
+#[snippet(name = "taylor_shift", include = "Binomial, convolution")]
+pub fn taylor_shift<const M: u32>(f: &[StaticModInt<M>], c: StaticModInt<M>) -> Vec<StaticModInt<M>> {
+  let n = f.len();
+  if n == 0 {
+    return vec![];
+  }
+  let mut binom = Binomial::<StaticModInt<M>>::new(n);
+  // f(x + c) の x^j の係数 = Σ_i f[i] i! c^(i-j) / ((i-j)! j!)
+  let a = (0..n).rev().map(|i| f[i] * binom.fact(i)).collect::<Vec<_>>();
+  let mut ck = StaticModInt::raw(1);
+  let b = (0..n)
+    .map(|k| {
+      let v = ck * binom.inv_fact(k);
+      ck *= c;
+      v
+    })
+    .collect::<Vec<_>>();
+  let ab = convolution(&a, &b);
+  (0..n).map(|j| ab[n - 1 - j] * binom.inv_fact(j)).collect()
+}
+
+/// f(0), ..., f(n-1) から f(m), ..., f(m+k-1) を求める (deg f < n <= M)
+/// O((n + k) log(n + k))
+#[snippet(name = "shift_of_sampling_points", include = "Binomial, convolution")]
+pub fn shift_of_sampling_points<const M: u32>(ys: &[StaticModInt<M>], m: u64, k: usize) -> Vec<StaticModInt<M>> {
+  let n = ys.len();
+  assert!(n <= M as usize);
+  let mut ret = Vec::with_capacity(k);
+  if n == 0 {
+    ret.resize(k, StaticModInt::raw(0));
+    return ret;
+  }
+  let mut binom = Binomial::<StaticModInt<M>>::new(n);
+  // f(x) = Σ_j a[j] Π_{l != j} (x - l)
+  let a = (0..n)
+    .map(|j| {
+      let v = ys[j] * binom.inv_fact(j) * binom.inv_fact(n - 1 - j);
+      if (n - 1 - j) % 2 == 1 {
+        -v
+      } else {
+        v
+      }
+    })
+    .collect::<Vec<_>>();
+  // f は mod M で周期 M なので m を M 未満にしておく
+  let mut m = m % M as u64;
+  while ret.len() < k {
+    let rest = k - ret.len();
+    if (m as usize) < n {
+      let cnt = rest.min(n - m as usize);
+      ret.extend_from_slice(&ys[m as usize..m as usize + cnt]);
+      m += cnt as u64;
+    } else {
+      // x - l (x = m..m+cnt, l = 0..n) が 0 にならない範囲
+      let cnt = rest.min((M as u64 - m) as usize);
+      ret.extend(shift_core(&a, m, cnt));
+      m += cnt as u64;
+    }
+    m %= M as u64;
+  }
+  ret
+}
+
+/// n <= m, m + cnt <= M のとき f(m), ..., f(m+cnt-1)
+#[snippet(name = "shift_of_sampling_points", include = "Binomial, convolution")]
+fn shift_core<const M: u32>(a: &[StaticModInt<M>], m: u64, cnt: usize) -> Vec<StaticModInt<M>> {
+  let n = a.len();
+  // v[t] = m - n + 1 + t (t = 0..n+cnt-1) は全て 0 でない
+  let len = n + cnt - 1;
+  let v = (0..len).map(|t| StaticModInt::new((m + 1 + t as u64 - n as u64) as i64)).collect::<Vec<_>>();
+  let mut prefix = vec![StaticModInt::raw(1); len + 1];
+  for t in 0..len {
+    prefix[t + 1] = prefix[t] * v[t];
+  }
+  // 逆数を一括で求める
+  let mut inv_prefix = vec![StaticModInt::raw(1); len + 1];
+  inv_prefix[len] = prefix[len].inv();
+  for t in (0..len).rev() {
+    inv_prefix[t] = inv_prefix[t + 1] * v[t];
+  }
+  let b = (0..len).map(|t| inv_prefix[t + 1] * prefix[t]).collect::<Vec<_>>();
+  // f(m+i) = Π_{l<n} (m+i-l) * Σ_j a[j] / (m+i-j)
+  let c = convolution(a, &b);
+  (0..cnt).map(|i| c[n - 1 + i] * prefix[i + n] * inv_prefix[i]).collect()
+}
+
+#[test]
+fn test_taylor_shift() {
+  use crate::math::modint::Mod;
+  let mut rng = crate::xorshift::Xorshift::new();
+  for n in [0, 1, 2, 10, 100] {
+    let f = (0..n).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+    let c = Mod::new(rng.rand(998244353) as i64);
+    let g = taylor_shift(&f, c);
+    assert_eq!(g.len(), n);
+    for _ in 0..5 {
+      let x = Mod::new(rng.rand(998244353) as i64);
+      let eval = |p: &[Mod], x: Mod| p.iter().rev().fold(Mod::new(0), |acc, &a| acc * x + a);
+      assert_eq!(eval(&g, x), eval(&f, x + c));
+    }
+  }
+  // (x + 1)^2
+  assert_eq!(
+    taylor_shift(&[Mod::new(0), Mod::new(0), Mod::new(1)], Mod::new(1)),
+    vec![Mod::new(1), Mod::new(2), Mod::new(1)]
+  );
+}
+
+#[test]
+fn test_shift_of_sampling_points() {
+  use crate::math::modint::{Mod, StaticModInt};
+  let mut rng = crate::xorshift::Xorshift::new();
+  let eval = |p: &[Mod], x: Mod| p.iter().rev().fold(Mod::new(0), |acc, &a| acc * x + a);
+  for n in [1, 2, 5, 40] {
+    let f = (0..n).map(|_| Mod::new(rng.rand(998244353) as i64)).collect::<Vec<_>>();
+    let ys = (0..n).map(|i| eval(&f, Mod::new(i as i64))).collect::<Vec<_>>();
+    for m in [0, 1, n as u64 - 1, n as u64, 1000, 998244353 - 3, 998244353 * 5 + 2, u64::MAX] {
+      for k in [1, 3, 50] {
+        let expected = (0..k).map(|i| eval(&f, Mod::new((m % 998244353) as i64 + i as i64))).collect::<Vec<_>>();
+        assert_eq!(shift_of_sampling_points(&ys, m, k), expected);
+      }
+    }
+  }
+  // 小さい法で周期をまたぐ場合
+  type M7 = StaticModInt<7>;
+  let f = [M7::new(3), M7::new(1), M7::new(4)];
+  let eval7 = |x: u64| f.iter().rev().fold(M7::new(0), |acc, &a| acc * M7::new(x as i64) + a);
+  let ys = (0..3).map(eval7).collect::<Vec<_>>();
+  let expected = (5..25).map(eval7).collect::<Vec<_>>();
+  assert_eq!(shift_of_sampling_points(&ys, 5, 20), expected);
+}